@@ -0,0 +1,396 @@
+use crate::cli::Args;
+use crate::geom::Point2;
+use crate::grid::Grid;
+#[cfg(test)]
+use crate::testing::SharedBuf;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// One picture of an animation, with a caption above it
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Frame {
+    pub caption: String,
+    pub lines: Vec<String>,
+}
+
+impl Frame {
+    pub fn new(caption: impl Into<String>, text: &str) -> Self {
+        Self {
+            caption: caption.into(),
+            lines: text.lines().map(str::to_owned).collect(),
+        }
+    }
+
+    pub fn from_grid<T>(
+        caption: impl Into<String>,
+        grid: &Grid<T>,
+        f: impl FnMut(&T) -> char,
+    ) -> Self {
+        Self::new(caption, &grid.render(f))
+    }
+
+    /// Draw the points as `#` on a background of `.`, cropped to the points
+    pub fn from_points(
+        caption: impl Into<String>,
+        points: impl IntoIterator<Item = Point2>,
+    ) -> Self {
+        let (_, grid) = Grid::from_points(points);
+        Self::from_grid(caption, &grid, |&lit| if lit { '#' } else { '.' })
+    }
+}
+
+/// Interactive control of a playing animation
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Command {
+    /// Pause, or resume from the latest frame
+    TogglePause,
+    /// Pause and show the next frame
+    Step,
+    /// Pause and show the previous frame from the scrollback
+    Back,
+    /// Halve the delay between frames
+    Faster,
+    /// Double the delay between frames
+    Slower,
+    /// Stop showing frames
+    Quit,
+}
+
+impl Command {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'p' | ' ' => Some(Command::TogglePause),
+            'n' => Some(Command::Step),
+            'b' => Some(Command::Back),
+            '+' => Some(Command::Faster),
+            '-' => Some(Command::Slower),
+            'q' => Some(Command::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// Read commands from stdin on a background thread. The terminal is left in
+/// line mode, so commands take effect when enter is pressed. An empty line
+/// steps one frame
+pub fn stdin_commands() -> Receiver<Command> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => return,
+            };
+            let mut commands: Vec<Command> = line.chars().filter_map(Command::from_char).collect();
+            if line.trim().is_empty() {
+                commands.push(Command::Step);
+            }
+            for command in commands {
+                if tx.send(command).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    rx
+}
+
+/// Plays frames in a terminal by redrawing them in place using ANSI escape
+/// codes.
+///
+/// Solvers call `show` for every step, which blocks for the delay between
+/// frames, or until the user steps forward when paused. The most recent
+/// frames are kept so that the user can step back through them. A disabled
+/// player ignores all frames, which is what solvers get unless animation is
+/// requested.
+pub struct Player {
+    out: Option<Box<dyn Write>>,
+    commands: Option<Receiver<Command>>,
+    delay: Duration,
+    paused: bool,
+    scrollback: usize,
+    history: VecDeque<Frame>,
+    num_dropped: usize,
+    position: usize,
+    num_drawn_lines: usize,
+}
+
+impl Player {
+    pub fn new(out: impl Write + 'static) -> Self {
+        Self {
+            out: Some(Box::new(out)),
+            commands: None,
+            delay: Duration::from_millis(100),
+            paused: false,
+            scrollback: 1000,
+            history: VecDeque::new(),
+            num_dropped: 0,
+            position: 0,
+            num_drawn_lines: 0,
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self {
+            out: None,
+            ..Self::new(io::sink())
+        }
+    }
+
+    /// Player on stderr that is controlled from stdin. Answers go to stdout,
+    /// so they can be piped without the escape codes of the frames
+    pub fn terminal(delay: Duration) -> Self {
+        Self::new(io::stderr())
+            .with_delay(delay)
+            .with_commands(stdin_commands())
+    }
+
+    /// Terminal player if `--animate` was given, otherwise disabled
+    pub fn from_args(args: &Args) -> Self {
+        match args.animate {
+            Some(delay) => Self::terminal(delay),
+            None => Self::disabled(),
+        }
+    }
+
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Number of frames that can be stepped back through
+    pub fn with_scrollback(mut self, num_frames: usize) -> Self {
+        self.scrollback = num_frames.max(1);
+        self
+    }
+
+    pub fn with_commands(mut self, commands: Receiver<Command>) -> Self {
+        self.commands = Some(commands);
+        self
+    }
+
+    /// False if the player is disabled or the user has quit
+    pub fn is_enabled(&self) -> bool {
+        self.out.is_some()
+    }
+
+    /// Show a frame and wait until it's time for the next one. Returns false
+    /// once the player is disabled, for example because the user quit
+    pub fn show(&mut self, frame: Frame) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+
+        if self.history.len() == self.scrollback {
+            self.history.pop_front();
+            self.num_dropped += 1;
+        }
+        self.history.push_back(frame);
+        self.position = self.history.len() - 1;
+        self.draw();
+        self.wait();
+        self.is_enabled()
+    }
+
+    /// Like `show`, but the frame is only created if it will be shown
+    pub fn show_with(&mut self, frame: impl FnOnce() -> Frame) -> bool {
+        self.is_enabled() && self.show(frame())
+    }
+
+    fn wait(&mut self) {
+        let deadline = Instant::now() + self.delay;
+        while self.is_enabled() {
+            let commands = match &self.commands {
+                Some(commands) => commands,
+                None => {
+                    thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                    return;
+                }
+            };
+
+            let received = if self.paused {
+                commands.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            } else {
+                commands.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            };
+            match received {
+                Ok(command) => {
+                    if self.apply(command) {
+                        return;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => return,
+                Err(RecvTimeoutError::Disconnected) => {
+                    // Nobody can unpause anymore, so keep playing
+                    self.commands = None;
+                    self.paused = false;
+                }
+            }
+        }
+    }
+
+    /// Returns true if the next frame should be shown
+    fn apply(&mut self, command: Command) -> bool {
+        let last = self.history.len() - 1;
+        match command {
+            Command::TogglePause => {
+                self.paused = !self.paused;
+                self.position = last;
+            }
+            Command::Step => {
+                self.paused = true;
+                if self.position == last {
+                    return true;
+                }
+                self.position += 1;
+            }
+            Command::Back => {
+                self.paused = true;
+                self.position = self.position.saturating_sub(1);
+            }
+            Command::Faster => self.delay /= 2,
+            Command::Slower => self.delay *= 2,
+            Command::Quit => {
+                self.finish();
+                return true;
+            }
+        }
+        self.draw();
+        false
+    }
+
+    fn draw(&mut self) {
+        let frame = &self.history[self.position];
+        let mut status = format!(
+            "frame {}/{}, {}ms",
+            self.num_dropped + self.position + 1,
+            self.num_dropped + self.history.len(),
+            self.delay.as_millis()
+        );
+        if self.paused {
+            status.push_str(", paused");
+        }
+        if self.commands.is_some() {
+            status.push_str(" | p: pause, n: next, b: back, +/-: speed, q: quit");
+        }
+
+        // Move back to where the previous frame started and draw over it
+        let mut text = String::new();
+        if self.num_drawn_lines > 0 {
+            text.push_str(&format!("\x1b[{}F", self.num_drawn_lines));
+        }
+        let lines = std::iter::once(&frame.caption)
+            .chain(frame.lines.iter())
+            .chain(std::iter::once(&status));
+        let mut num_lines = 0;
+        for line in lines {
+            text.push_str(line);
+            text.push_str("\x1b[K\n");
+            num_lines += 1;
+        }
+        text.push_str("\x1b[J");
+        self.num_drawn_lines = num_lines;
+
+        // Errors are ignored since the animation is purely informational
+        if let Some(out) = self.out.as_mut() {
+            let _ = out.write_all(text.as_bytes());
+            let _ = out.flush();
+        }
+    }
+
+    /// Stop showing frames. The last frame is left on screen
+    pub fn finish(&mut self) {
+        self.out = None;
+        self.commands = None;
+    }
+}
+
+/// Captions of all drawn frames, in order
+#[cfg(test)]
+fn drawn_captions(buf: &SharedBuf) -> Vec<String> {
+    buf.text()
+        .split("\x1b[J")
+        .filter(|draw| !draw.is_empty())
+        .map(|draw| {
+            let draw = match draw.find('F') {
+                Some(i) if draw.starts_with("\x1b[") => &draw[i + 1..],
+                _ => draw,
+            };
+            draw.split("\x1b[K").next().unwrap().to_owned()
+        })
+        .collect()
+}
+
+#[test]
+fn test_frames() {
+    let grid = Grid::from_fn(3, 2, |x, y| x == y);
+    assert_eq!(
+        Frame::from_grid("grid", &grid, |&b| if b { '#' } else { ' ' }),
+        Frame {
+            caption: "grid".to_owned(),
+            lines: vec!["#  ".to_owned(), " # ".to_owned()],
+        }
+    );
+    assert_eq!(
+        Frame::from_points("stars", vec![Point2::new(5, 5), Point2::new(6, 7)]).lines,
+        vec!["#.", "..", ".#"]
+    );
+}
+
+#[test]
+fn test_controls() {
+    let buf = SharedBuf::new();
+    let (tx, rx) = mpsc::channel();
+    let mut player = Player::new(buf.clone())
+        .with_delay(Duration::from_millis(0))
+        .with_commands(rx);
+
+    assert!(player.show(Frame::new("a", "1")));
+
+    // Pausing redraws the frame, then the user goes back to the first frame
+    // and steps forward twice, which lets the next frame through
+    for &c in &[
+        Command::TogglePause,
+        Command::Back,
+        Command::Step,
+        Command::Step,
+    ] {
+        tx.send(c).unwrap();
+    }
+    assert!(player.show(Frame::new("b", "2")));
+
+    tx.send(Command::Quit).unwrap();
+    assert!(!player.show(Frame::new("c", "3")));
+    assert!(!player.is_enabled());
+    assert!(!player.show(Frame::new("d", "4")));
+
+    assert_eq!(drawn_captions(&buf), vec!["a", "b", "b", "a", "b", "c"]);
+}
+
+#[test]
+fn test_scrollback() {
+    let buf = SharedBuf::new();
+    let (tx, rx) = mpsc::channel();
+    let mut player = Player::new(buf.clone())
+        .with_delay(Duration::from_millis(0))
+        .with_scrollback(2)
+        .with_commands(rx);
+
+    for caption in &["a", "b"] {
+        player.show(Frame::new(*caption, ""));
+    }
+    tx.send(Command::Back).unwrap();
+    tx.send(Command::Back).unwrap();
+    tx.send(Command::TogglePause).unwrap();
+    player.show(Frame::new("c", ""));
+
+    // Only b and c are kept, and resuming jumps to the latest frame
+    assert_eq!(drawn_captions(&buf), vec!["a", "b", "c", "b", "b", "c"]);
+
+    let out = buf.text();
+    assert!(out.contains("frame 2/3, 0ms, paused"));
+    assert!(!Player::disabled().show_with(|| unreachable!()));
+}
@@ -0,0 +1,91 @@
+use aoc::animate::{Frame, Player};
+use aoc::cli::args;
+use aoc::json::Value;
+use aoc::record::{self, location};
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::process;
+
+/// The record's location as caption and one line per field. Strings are
+/// shown without quotes, so that rows of pots look like they do in the puzzle
+fn frame(record: &Value) -> Frame {
+    let lines = match record {
+        Value::Object(fields) => fields
+            .iter()
+            .filter(|(key, _)| key != "part" && key != "step")
+            .map(|(key, value)| match value {
+                Value::String(s) => format!("{}: {}", key, s),
+                value => format!("{}: {}", key, value),
+            })
+            .collect(),
+        value => vec![value.to_string()],
+    };
+    Frame {
+        caption: location(record),
+        lines,
+    }
+}
+
+fn open(path: &str) -> io::Result<BufReader<File>> {
+    File::open(Path::new(path))
+        .map(BufReader::new)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))
+}
+
+/// Print every record, or play them as an animation if `--animate` is given
+fn replay(path: &str) -> Result<(), Box<dyn Error>> {
+    let mut animation = Player::from_args(args());
+    for record in record::read(open(path)?) {
+        let frame = frame(&record?);
+        if animation.is_enabled() {
+            if !animation.show(frame) {
+                break;
+            }
+        } else {
+            println!("{}: {}", frame.caption, frame.lines.join(", "));
+        }
+    }
+    animation.finish();
+    Ok(())
+}
+
+/// Exits with status 1 if the recordings differ, like `diff`
+fn compare(left: &str, right: &str) -> Result<(), Box<dyn Error>> {
+    match record::first_difference(open(left)?, open(right)?)? {
+        Some(difference) => {
+            println!("{}", difference);
+            process::exit(1);
+        }
+        None => println!("Recordings are identical"),
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = args();
+    let positional: Vec<&str> = args.positional.iter().map(String::as_str).collect();
+    match positional.as_slice() {
+        ["replay", path] => replay(path),
+        ["replay", left, right] => compare(left, right),
+        _ => {
+            println!("{}", args.usage("replay <recording> [<other-recording>]"));
+            process::exit(2);
+        }
+    }
+}
+
+#[test]
+fn test_frame() {
+    let record: Value = r##"{"part":"A","step":3,"gen":3,"pots":"#.#"}"##
+        .parse()
+        .unwrap();
+    assert_eq!(
+        frame(&record),
+        Frame {
+            caption: "part A, step 3".to_owned(),
+            lines: vec!["gen: 3".to_owned(), "pots: #.#".to_owned()],
+        }
+    );
+}
@@ -1,32 +1,94 @@
-use aoc::{buf_reader_from_arg, parse_lines};
-use std::collections::HashSet;
+use aoc::input::{self, InputFormat, Record};
+use aoc::math;
+use aoc::parse::{parse_value, ParseError};
+use aoc::string_from_arg;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+
+fn change_from_record(record: &Record) -> Result<i32, ParseError> {
+    record.get("change")
+}
 
 fn part_a(changes: impl Iterator<Item = i32>) -> i32 {
     changes.sum()
 }
 
-fn part_b(changes: impl Iterator<Item = i32>) -> i32 {
-    let mut current: i32 = 0;
-    let mut memory = HashSet::new();
-    memory.insert(current);
+/// First frequency reached twice, or None if the frequencies keep drifting
+/// away without ever repeating
+fn part_b(changes: impl Iterator<Item = i32>) -> Option<i64> {
+    // Frequency before each change during the first pass. Every later pass
+    // visits the same frequencies moved by the total drift
+    let mut frequencies = vec![0i64];
+    for change in changes {
+        frequencies.push(frequencies.last().unwrap() + i64::from(change));
+    }
+    let drift = frequencies.pop().unwrap();
+    let n = frequencies.len() as i64;
 
-    for change in changes.collect::<Vec<i32>>().iter().cycle() {
-        current += change;
-        if !memory.insert(current) {
-            return current;
+    // A repeat during the first pass always comes before any later one
+    let mut seen = HashSet::new();
+    for &frequency in &frequencies {
+        if !seen.insert(frequency) {
+            return Some(frequency);
         }
     }
-    unreachable!();
+    if drift == 0 {
+        return Some(0);
+    }
+
+    // Writing each frequency as pass * drift + residue, frequency i reaches
+    // frequency j after the difference of their passes if the residues are
+    // equal. Within each residue class the next pass is reached first
+    let mut classes: HashMap<i64, Vec<(i64, i64)>> = HashMap::new();
+    for (i, &frequency) in frequencies.iter().enumerate() {
+        let (pass, residue) = math::div_rem_euclid(frequency, drift);
+        classes.entry(residue).or_default().push((pass, i as i64));
+    }
+
+    let mut first: Option<(i64, i64)> = None;
+    for (residue, class) in classes.iter_mut() {
+        class.sort_unstable();
+        for pair in class.windows(2) {
+            let ((from, i), (to, _)) = (pair[0], pair[1]);
+            let step = (to - from) * n + i;
+            if first.map_or(true, |(best, _)| step < best) {
+                first = Some((step, to * drift + residue));
+            }
+        }
+    }
+    first.map(|(_, frequency)| frequency)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let input = string_from_arg()?;
+    let format = InputFormat::from_args(aoc::cli::args());
+    let changes = input::parse_items(&input, format, parse_value, change_from_record)?;
+    println!("Answer A: {}", part_a(changes.iter().cloned()));
+    let repeat = part_b(changes.into_iter()).ok_or("Frequencies never repeat")?;
+    println!("Answer B: {}", repeat);
+    Ok(())
 }
 
-fn main() {
-    println!(
-        "Answer A: {}",
-        part_a(parse_lines::<i32>(buf_reader_from_arg().unwrap()))
+#[test]
+fn test_parse_structured() {
+    let parse = |input, format| input::parse_items(input, format, parse_value, change_from_record);
+    let expected = Ok(vec![1, -2, 3]);
+
+    assert_eq!(parse("+1\n-2\n+3\n", InputFormat::Text), expected);
+    assert_eq!(
+        parse(
+            r#"[{"change": 1}, {"change": -2}, {"change": "+3"}]"#,
+            InputFormat::Json
+        ),
+        expected
     );
-    println!(
-        "Answer B: {}",
-        part_b(parse_lines::<i32>(buf_reader_from_arg().unwrap()))
+    assert_eq!(parse("change\n+1\n-2\n3\n", InputFormat::Csv), expected);
+    assert_eq!(
+        parse("+1\nx\n", InputFormat::Text),
+        Err(ParseError::AtLine {
+            line: 2,
+            error: Box::new(ParseError::NoMatch("x".to_owned()))
+        })
     );
 }
 
@@ -37,5 +99,12 @@ fn test_a() {
 
 #[test]
 fn test_b() {
-    assert_eq!(part_b(vec![1i32, -2, 3, 1].into_iter()), 2);
+    assert_eq!(part_b(vec![1i32, -2, 3, 1].into_iter()), Some(2));
+    assert_eq!(part_b(vec![1i32, -1].into_iter()), Some(0));
+    assert_eq!(part_b(vec![3i32, 3, 4, -2, -4].into_iter()), Some(10));
+    assert_eq!(part_b(vec![-6i32, 3, 8, 5, -6].into_iter()), Some(5));
+    assert_eq!(part_b(vec![7i32, 7, -2, -7, -4].into_iter()), Some(14));
+    assert_eq!(part_b(vec![-7i32, -7, 2, 7, 4].into_iter()), Some(-14));
+    assert_eq!(part_b(vec![1i32].into_iter()), None);
+    assert_eq!(part_b(vec![1i32, 1, 1].into_iter()), None);
 }
@@ -1,43 +1,44 @@
+use aoc::geom::Point2;
 use aoc::get_arg;
-use std::iter::repeat;
+use aoc::grid::Grid;
+use aoc::image::{self, ColorMap};
+use aoc::prefix::SummedArea;
+use std::error::Error;
 
 fn extract_base10_digit(mut value: usize, index: u32) -> usize {
     value /= 10usize.pow(index);
     value % 10
 }
 
-fn grid_iterator(
-    (sx, sy): (usize, usize),
-    (ex, ey): (usize, usize),
-) -> impl Iterator<Item = (usize, usize)> {
-    (sx..=ex).flat_map(move |x| repeat(x).zip(sy..=ey))
-}
-
 fn fuel_cell_value(serial: usize, x: usize, y: usize) -> isize {
     let rack_id = x + 10;
     extract_base10_digit((y * rack_id + serial) * rack_id, 2) as isize - 5
 }
 
-fn fuel_cell_group_sum(serial: usize, x: usize, y: usize) -> isize {
-    grid_iterator((x, y), (x + 2, y + 2))
-        .map(|(x, y)| fuel_cell_value(serial, x, y))
-        .sum::<isize>()
+/// Summed power levels of the 300x300 fuel cells. Since the puzzle
+/// coordinates start at 1 the cell at `(x, y)` is found at `(x - 1, y - 1)` in
+/// the table
+fn fuel_cell_table(serial: usize) -> SummedArea<isize> {
+    SummedArea::from_fn(300, 300, |x, y| fuel_cell_value(serial, x + 1, y + 1))
 }
 
-fn part_a(serial: usize) -> (usize, usize) {
-    // We end at x or y = 298 since that's the top left corner of the last fuel
-    // cell group
-    let (_, x, y) = grid_iterator((1, 1), (298, 298))
-        .map(|(x, y)| (fuel_cell_group_sum(serial, x, y), x, y))
-        .max()
-        .unwrap();
-    (x, y)
+fn part_a(serial: usize) -> Point2 {
+    let (_, x, y) = fuel_cell_table(serial).best_square(3).unwrap();
+    Point2::new(x as i64 + 1, y as i64 + 1)
 }
 
-fn main() {
-    let serial: usize = get_arg().unwrap();
-    let (x, y) = part_a(serial);
-    println!("Answer A: {},{}", x, y);
+fn main() -> Result<(), Box<dyn Error>> {
+    let serial: usize = get_arg()?;
+    println!("Answer A: {}", part_a(serial));
+
+    if let Some(path) = &aoc::cli::args().image {
+        let cells = Grid::from_fn(300, 300, |x, y| fuel_cell_value(serial, x + 1, y + 1));
+        image::save(
+            &image::heatmap(&cells, ColorMap::Diverging, |&v| v as f64),
+            path,
+        )?;
+    }
+    Ok(())
 }
 
 #[test]
@@ -56,10 +57,13 @@ fn test_fuel_cell_value() {
 }
 
 #[test]
-fn test_fuel_cell_group_sum() {
-    assert_eq!(fuel_cell_group_sum(18, 33, 45), 29);
-    assert_eq!(fuel_cell_group_sum(42, 21, 61), 30);
+fn test_fuel_cell_table() {
+    assert_eq!(fuel_cell_table(18).sum(32, 44, 3, 3), 29);
+    assert_eq!(fuel_cell_table(42).sum(20, 60, 3, 3), 30);
 }
 
 #[test]
-fn test_a() {}
+fn test_a() {
+    assert_eq!(part_a(18), Point2::new(33, 45));
+    assert_eq!(part_a(42), Point2::new(21, 61));
+}
@@ -1,8 +1,23 @@
-use aoc::{buf_reader_from_arg, parse_lines};
+use aoc::animate::Frame;
+use aoc::collections::BitRow;
+use aoc::cycle::CycleDetector;
+use aoc::grid::Grid;
+use aoc::image::{self, ImageSequence};
+use aoc::input::{self, InputFormat, Record};
+use aoc::math;
+use aoc::overflow::{self, Overflow, OverflowError};
+use aoc::parse::{parse_captures, Block, ParseError};
+use aoc::run::RunContext;
+use aoc::string_from_arg;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::error::Error;
 use std::fmt;
-use std::collections::VecDeque;
+use std::io;
+use std::path::Path;
 use std::str::FromStr;
 
+#[derive(Default, PartialEq)]
 struct PotArea(u8);
 
 impl PotArea {
@@ -31,12 +46,6 @@ impl PotArea {
     }
 }
 
-impl Default for PotArea {
-    fn default() -> Self {
-        PotArea(0)
-    }
-}
-
 impl fmt::Debug for PotArea {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut repr = String::with_capacity(5);
@@ -48,13 +57,36 @@ impl fmt::Debug for PotArea {
 }
 
 impl FromStr for PotArea {
-    type Err = ();
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let pots: Vec<_> = s[0..5].chars().map(|x| x == '#').collect();
+        if s.len() != 5 || !s.chars().all(|x| x == '#' || x == '.') {
+            return Err(ParseError::NoMatch(s.to_owned()));
+        }
+        let pots: Vec<_> = s.chars().map(|x| x == '#').collect();
         Ok(PotArea::new(pots[0], pots[1], pots[2], pots[3], pots[4]))
     }
 }
 
+#[derive(Debug, PartialEq)]
+struct Rule {
+    area: PotArea,
+    grows: bool,
+}
+
+impl FromStr for Rule {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        lazy_static! {
+            static ref rule_re: Regex = Regex::new(r"^([#.]{5}) => ([#.])$").unwrap();
+        }
+        let (area, grows): (String, char) = parse_captures(&rule_re, s)?;
+        Ok(Rule {
+            area: area.parse()?,
+            grows: grows == '#',
+        })
+    }
+}
+
 #[derive(Debug)]
 struct PotMap {
     map: [bool; 32],
@@ -80,122 +112,277 @@ impl PotMap {
 
 #[derive(Clone)]
 struct Pots {
-    offset: isize,
-    pots: VecDeque<bool>,
+    pots: BitRow,
 }
 
 impl Pots {
     fn from_iter(offset: isize, it: impl Iterator<Item = bool>) -> Self {
-        let pots = it.collect();
-        Pots { offset, pots }.normalize()
+        let mut pots = BitRow::from_bools(offset, it);
+        pots.normalize();
+        Pots { pots }
     }
 
-    fn normalize(mut self) -> Self {
-        while self.pots.len() > 0 && self.pots.front() != Some(&true) {
-            self.pots.pop_front();
-            self.offset += 1;
-        }
-
-        while self.pots.len() > 0 && self.pots.back() != Some(&true) {
-            self.pots.pop_back();
-        }
-
-        self
+    /// Index of the leftmost pot with a plant
+    fn offset(&self) -> isize {
+        self.pots.first().unwrap_or(0)
     }
 
     fn has_pot(&self, index: isize) -> bool {
-        if index < self.offset {
-            return false;
-        }
-        *self.pots.get((index - self.offset) as usize).unwrap_or(&false)
+        self.pots.get(index)
     }
 
-    fn sum(&self) -> isize {
-        self.pots.iter().enumerate().filter_map(|(i, has_pot)| {
-            if *has_pot {
-                Some(i as isize + self.offset)
-            } else {
-                None
-            }
-        }).sum()
+    fn sum(&self, overflow: Overflow) -> Result<isize, OverflowError> {
+        self.pots.index_sum(overflow)
     }
 }
 
 impl fmt::Debug for Pots {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let repr: String = self.pots.iter().map(|&has_pot| if has_pot {
-            '#'
-        } else {
-            '.'
-        }).collect();
-        write!(f, "Pots<{}, {}>", self.offset, repr)
+        write!(f, "Pots<{}, {}>", self.offset(), self.pots)
     }
 }
 
-fn simulate(gens: usize, pots: Pots, pot_map: &PotMap) -> Pots {
-    let mut history: Vec<Pots> = Vec::new();
+fn step(prev: &Pots, pot_map: &PotMap) -> Pots {
+    let range = match prev.pots.range() {
+        Some(range) => range,
+        None => return prev.clone(),
+    };
+    let indices = range.start - 2..range.end + 2;
+    Pots::from_iter(indices.start, indices.scan(PotArea::default(), |pot_area, i| {
+        *pot_area = pot_area.advance(prev.has_pot(i + 2));
+        Some(pot_map.can_grow(pot_area))
+    }))
+}
+
+fn frame(gen: usize, pots: &Pots) -> Frame {
+    let caption = format!("Generation {}, first pot at {}", gen, pots.offset());
+    Frame::new(caption, &pots.pots.to_string())
+}
+
+fn simulate(
+    gens: usize,
+    pots: Pots,
+    pot_map: &PotMap,
+    ctx: &mut RunContext,
+) -> Result<Pots, OverflowError> {
+    let overflow = ctx.overflow;
+
+    // Configurations are compared by pattern only, not by offset, since the
+    // same pattern will keep growing the same way wherever it is
+    let mut history = CycleDetector::new(|pots: &Pots| pots.pots.pattern());
+    aoc::trace!(Info, "generation", gen = 0, pots = format!("{:?}", pots));
+    ctx.animation.show_with(|| frame(0, &pots));
+    aoc::record!(
+        ctx.recorder,
+        gen = 0,
+        offset = pots.offset(),
+        pots = pots.pots.to_string()
+    );
     history.push(pots);
+    ctx.progress.set_total(Some(gens as u64));
+
+    for gen in 1..=gens {
+        let curr = step(history.last().unwrap(), pot_map);
+        aoc::trace!(Info, "generation", gen = gen, pots = format!("{:?}", curr));
+        ctx.animation.show_with(|| frame(gen, &curr));
+        aoc::record!(
+            ctx.recorder,
+            gen = gen,
+            offset = curr.offset(),
+            pots = curr.pots.to_string()
+        );
+
+        if let Some(cycle) = history.push(curr) {
+            // How much the offset increases per cycle
+            let offset_delta = cycle.delta(history.states(), |pots| pots.offset());
+            // Speed of the pattern in pots per generation, as a reduced fraction
+            let (delta, length) = (offset_delta as i64, cycle.length as i64);
+            let divisor = math::gcd(delta, length);
+            aoc::trace!(
+                Info,
+                "cycle",
+                start = cycle.start,
+                length = cycle.length,
+                offset_delta = offset_delta,
+                speed = format!("{}/{}", delta / divisor, length / divisor),
+            );
 
-    for _ in 0..gens {
-        let prev = &history[history.len() - 1];
-        let indices = prev.offset - 2..prev.offset + prev.pots.len() as isize + 2;
-        let curr = Pots::from_iter(indices.start, indices.scan(PotArea::default(), |pot_area, i| {
-            *pot_area = pot_area.advance(prev.has_pot(i + 2));
-            Some(pot_map.can_grow(pot_area))
-        }));
-
-        // See if we have observed this particular plant configuration before.
-        // This is only sensitive to the pattern, not the offset.
-        for (prev_gen, prev) in history.iter().enumerate().rev() {
-            if prev.pots != curr.pots {
-                continue;
-            }
+            ctx.progress.set(gens as u64);
+            ctx.progress.finish();
+            return cycle.extrapolate(history.states(), gens, |pots, num_cycles| {
+                let mut pots = pots.clone();
+                let num_cycles = overflow::convert(num_cycles)?;
+                let by = overflow.mul(num_cycles, offset_delta)?;
+                pots.pots.shift(by, overflow)?;
+                Ok(pots)
+            });
+        }
+        ctx.progress.inc(1);
+    }
+    ctx.progress.finish();
 
-            let curr_gen = history.len();
+    Ok(history.into_states().pop().unwrap())
+}
 
-            // How much the offset increases per cycle
-            let offset_inc_per_cycle = curr.offset - prev.offset;
-
-            // TODO: Cycle lengths other than 1 are not handled properly
-            let cycle_length = curr_gen - prev_gen;
-            assert!(cycle_length == 1, "Cycle length must be no longer than 1");
-            let num_cycles = (gens - prev_gen) / cycle_length;
-
-            let interpolated_pots = Pots {
-                offset: prev.offset + (num_cycles as isize * offset_inc_per_cycle),
-                pots: history[prev_gen].pots.clone(),
-            };
-            return interpolated_pots
+/// Space-time diagram of the first generations, one row of pixels per
+/// generation with plants in black
+fn space_time(pots: Pots, pot_map: &PotMap, gens: usize) -> Grid<u8> {
+    let mut history = vec![pots];
+    for _ in 0..gens {
+        history.push(step(history.last().unwrap(), pot_map));
+    }
+
+    let ranges: Vec<_> = history.iter().filter_map(|p| p.pots.range()).collect();
+    let start = ranges.iter().map(|r| r.start).min().unwrap_or(0);
+    let end = ranges.iter().map(|r| r.end).max().unwrap_or(0);
+    Grid::from_fn((end - start) as usize, history.len(), |x, y| {
+        if history[y].has_pot(start + x as isize) {
+            0
+        } else {
+            255
         }
+    })
+}
 
-        history.push(curr);
+/// Save the space-time diagram as it grows, with one more generation in
+/// every image
+fn save_generations(diagram: &Grid<u8>, dir: &Path) -> io::Result<()> {
+    let mut sequence = ImageSequence::new(dir, "gen")?;
+    for gen in 0..diagram.height() {
+        sequence.push(&Grid::from_fn(diagram.width(), diagram.height(), |x, y| {
+            if y <= gen {
+                diagram[(x, y)]
+            } else {
+                255
+            }
+        }))?;
     }
+    Ok(())
+}
 
-    history.into_iter().last().unwrap()
+fn part_a(pots: Pots, pot_map: &PotMap, ctx: &mut RunContext) -> Result<isize, OverflowError> {
+    simulate(20, pots, pot_map, ctx)?.sum(ctx.overflow)
 }
 
-fn part_a(pots: Pots, pot_map: &PotMap) -> isize {
-    simulate(20, pots, pot_map).sum()
+fn part_b(pots: Pots, pot_map: &PotMap, ctx: &mut RunContext) -> Result<isize, OverflowError> {
+    simulate(50_000_000_000, pots, pot_map, ctx)?.sum(ctx.overflow)
 }
 
-fn part_b(pots: Pots, pot_map: &PotMap) -> isize {
-    simulate(50_000_000_000, pots, pot_map).sum()
+fn parse_text(input: &str) -> Result<(String, Vec<Rule>), ParseError> {
+    lazy_static! {
+        static ref state_re: Regex = Regex::new(r"^initial state: ([#.]+)$").unwrap();
+    }
+    let (header, body) = Block::new(input).header_body()?;
+    let (initial_state,): (String,) =
+        parse_captures(&state_re, header.text).map_err(|e| header.error(e))?;
+    Ok((initial_state, body.parse_lines()?))
 }
 
-fn main() {
-    let mut data = parse_lines::<String>(buf_reader_from_arg().unwrap());
+fn rule_from_record(record: &Record) -> Result<Rule, ParseError> {
+    let grows = match record.get::<String>("grows")?.as_str() {
+        "#" => true,
+        "." => false,
+        grows => {
+            return Err(ParseError::InvalidField {
+                field: "grows".to_owned(),
+                value: grows.to_owned(),
+            })
+        }
+    };
+    Ok(Rule {
+        area: record.get("area")?,
+        grows,
+    })
+}
 
-    let initial_state_str = data.next().unwrap();
-    data.next();
-    let pots = Pots::from_iter(0, initial_state_str[15..].chars().map(|x| x == '#'));
+/// One record has the `initial_state`, the others are rules with the fields
+/// `area` and `grows`, written with `#` and `.` like in the puzzle text
+fn parse_records(input: &str, format: InputFormat) -> Result<(String, Vec<Rule>), ParseError> {
+    let mut initial_state = None;
+    let mut rules = Vec::new();
+    for record in input::records(input, format)? {
+        let state = match record.get_opt::<String>("initial_state") {
+            Ok(Some(state)) => state,
+            Ok(None) => {
+                rules.push(rule_from_record(&record).map_err(|e| record.error(e))?);
+                continue;
+            }
+            Err(e) => return Err(record.error(e)),
+        };
+        if initial_state.is_some() || !state.chars().all(|x| x == '#' || x == '.') {
+            return Err(record.error(ParseError::InvalidField {
+                field: "initial_state".to_owned(),
+                value: state,
+            }));
+        }
+        initial_state = Some(state);
+    }
+    let initial_state =
+        initial_state.ok_or_else(|| ParseError::MissingField("initial_state".to_owned()))?;
+    Ok((initial_state, rules))
+}
 
-    let pot_map = PotMap::from_iter(data
-        .filter(|x| x.chars().last() == Some('#'))
-        .map(|x| x.parse::<PotArea>().unwrap())
-    );
+fn main() -> Result<(), Box<dyn Error>> {
+    let input = string_from_arg()?;
+    let (initial_state, rules) = match InputFormat::from_args(aoc::cli::args()) {
+        InputFormat::Text => parse_text(&input)?,
+        format => parse_records(&input, format)?,
+    };
+    let pots = Pots::from_iter(0, initial_state.chars().map(|x| x == '#'));
+    let pot_map = PotMap::from_iter(rules.into_iter().filter(|r| r.grows).map(|r| r.area));
+
+    let args = aoc::cli::args();
+    let mut ctx = RunContext::from_args(args)?;
+    ctx.part("A");
+    println!("Answer A: {}", part_a(pots.clone(), &pot_map, &mut ctx)?);
+    ctx.part("B");
+    println!("Answer B: {}", part_b(pots.clone(), &pot_map, &mut ctx)?);
+    ctx.finish()?;
+
+    if args.image.is_some() || args.image_sequence.is_some() {
+        // Enough generations for the plants to settle into their cycle
+        let diagram = space_time(pots, &pot_map, 200);
+        if let Some(path) = &args.image {
+            image::save(&diagram, path)?;
+        }
+        if let Some(dir) = &args.image_sequence {
+            save_generations(&diagram, dir)?;
+        }
+    }
+    Ok(())
+}
 
-    println!("Answer A: {}", part_a(pots.clone(), &pot_map));
-    println!("Answer B: {}", part_b(pots, &pot_map));
+#[test]
+fn test_parse_structured() {
+    let text = "initial state: #..#.#\n\n...## => #\n..#.. => .\n";
+    let json = r##"[
+        {"initial_state": "#..#.#"},
+        {"area": "...##", "grows": "#"},
+        {"area": "..#..", "grows": "."}
+    ]"##;
+    let csv = "initial_state,area,grows\n#..#.#,,\n,...##,#\n,..#..,.\n";
+
+    let expected = parse_text(text).unwrap();
+    assert_eq!(expected.1.len(), 2);
+    assert_eq!(
+        parse_records(json, InputFormat::Json).as_ref(),
+        Ok(&expected)
+    );
+    assert_eq!(parse_records(csv, InputFormat::Csv), Ok(expected));
+    assert_eq!(
+        parse_records("area,grows\n...##,x\n", InputFormat::Csv),
+        Err(ParseError::AtLine {
+            line: 2,
+            error: Box::new(ParseError::InvalidField {
+                field: "grows".to_owned(),
+                value: "x".to_owned()
+            })
+        })
+    );
+    assert_eq!(
+        parse_records("area,grows\n...##,#\n", InputFormat::Csv),
+        Err(ParseError::MissingField("initial_state".to_owned()))
+    );
 }
 
 #[test]
@@ -223,9 +410,9 @@ fn test_part_a() {
         patterns.iter().map(|x| x.parse::<PotArea>().unwrap())
     );
 
-    let future = simulate(20, pots, &pot_map);
-    assert_eq!(future.offset, -2);
-    assert_eq!(future.sum(), 325);
+    let future = simulate(20, pots, &pot_map, &mut RunContext::silent()).unwrap();
+    assert_eq!(future.offset(), -2);
+    assert_eq!(future.sum(Overflow::Checked), Ok(325));
 }
 
 #[test]
@@ -233,15 +420,46 @@ fn test_cycle_shortcut() {
     let state = "##";
     let pots = Pots::from_iter(0, state.chars().map(|x| x == '#'));
 
-    let patterns = vec![
-        ".##..",
-        "##...",
-    ];
+    let patterns = [".##..", "##..."];
+    let pot_map = PotMap::from_iter(
+        patterns.iter().map(|x| x.parse::<PotArea>().unwrap())
+    );
+
+    let future = simulate(50_000_000_000, pots, &pot_map, &mut RunContext::silent()).unwrap();
+    assert_eq!(future.offset(), 50_000_000_000);
+    assert_eq!(future.sum(Overflow::Checked), Ok(100_000_000_001));
+}
+
+#[test]
+fn test_cycle_length_two() {
+    // Alternates between # and ## while moving two pots every other generation
+    let pots = Pots::from_iter(0, "#".chars().map(|x| x == '#'));
+    let patterns = ["..#..", ".#...", "##..."];
     let pot_map = PotMap::from_iter(
         patterns.iter().map(|x| x.parse::<PotArea>().unwrap())
     );
 
-    let future = simulate(50_000_000_000, pots, &pot_map);
-    assert_eq!(future.offset, 50_000_000_000);
-    assert_eq!(future.sum(), 100_000_000_001);
+    let future =
+        simulate(50_000_000_000, pots.clone(), &pot_map, &mut RunContext::silent()).unwrap();
+    assert_eq!(future.offset(), 50_000_000_000);
+    assert_eq!(future.sum(Overflow::Checked), Ok(50_000_000_000));
+
+    let future = simulate(1_000_001, pots, &pot_map, &mut RunContext::silent()).unwrap();
+    assert_eq!(future.offset(), 1_000_000);
+    assert_eq!(future.sum(Overflow::Checked), Ok(2_000_001));
+}
+
+#[test]
+fn test_space_time() {
+    let pots = Pots::from_iter(0, "##".chars().map(|x| x == '#'));
+    let patterns = [".##..", "##..."];
+    let pot_map = PotMap::from_iter(
+        patterns.iter().map(|x| x.parse::<PotArea>().unwrap())
+    );
+
+    let diagram = space_time(pots, &pot_map, 2);
+    assert_eq!(
+        diagram.render(|&p| if p == 0 { '#' } else { '.' }),
+        "##..\n.##.\n..##\n"
+    );
 }
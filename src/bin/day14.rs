@@ -1,4 +1,6 @@
 use aoc::get_arg;
+use aoc::run::RunContext;
+use std::error::Error;
 
 fn parse_digits(number: &str) -> Vec<u8> {
     number
@@ -20,7 +22,9 @@ fn extend_recipes(recipes: &mut Vec<u8>, new_recipes: u8) -> bool {
     }
 }
 
-fn part_a(recipe_index: usize) -> String {
+fn part_a(recipe_index: usize, ctx: &mut RunContext) -> String {
+    ctx.progress.set_total(Some(recipe_index as u64 + 10));
+
     let mut elf_a = 0;
     let mut elf_b = 1;
     let mut recipes: Vec<u8> = Vec::with_capacity(recipe_index + 10);
@@ -34,14 +38,22 @@ fn part_a(recipe_index: usize) -> String {
         // Find new recipes
         elf_a = (elf_a + recipes[elf_a] as usize + 1) % recipes.len();
         elf_b = (elf_b + recipes[elf_b] as usize + 1) % recipes.len();
+        aoc::record!(
+            ctx.recorder,
+            score = new_recipes,
+            recipes = recipes.len(),
+            elves = vec![elf_a, elf_b],
+        );
+        ctx.progress.set(recipes.len() as u64);
     }
+    ctx.progress.finish();
     recipes[recipe_index..recipe_index + 10]
         .iter()
         .map(|x| x.to_string())
         .collect::<String>()
 }
 
-fn part_b(sequence: &str) -> usize {
+fn part_b(sequence: &str, ctx: &mut RunContext) -> usize {
     let mut elf_a = 0;
     let mut elf_b = 1;
     let mut recipes: Vec<u8> = vec![3, 7];
@@ -54,6 +66,13 @@ fn part_b(sequence: &str) -> usize {
         // Find new recipes
         elf_a = (elf_a + recipes[elf_a] as usize + 1) % recipes.len();
         elf_b = (elf_b + recipes[elf_b] as usize + 1) % recipes.len();
+        aoc::record!(
+            ctx.recorder,
+            score = new_recipes,
+            recipes = recipes.len(),
+            elves = vec![elf_a, elf_b],
+        );
+        ctx.progress.set(recipes.len() as u64);
 
         if recipes.len() < sequence.len() {
             continue;
@@ -71,11 +90,16 @@ fn part_b(sequence: &str) -> usize {
     }
 }
 
-fn main() {
-    let arg: String = get_arg().unwrap();
+fn main() -> Result<(), Box<dyn Error>> {
+    let arg: String = get_arg()?;
+    let mut ctx = RunContext::from_args(aoc::cli::args())?;
 
-    println!("Answer A: {}", part_a(arg.parse::<usize>().unwrap()));
-    println!("Answer B: {}", part_b(&arg));
+    ctx.part("A");
+    println!("Answer A: {}", part_a(arg.parse::<usize>()?, &mut ctx));
+    ctx.part("B");
+    println!("Answer B: {}", part_b(&arg, &mut ctx));
+    ctx.finish()?;
+    Ok(())
 }
 
 #[test]
@@ -88,15 +112,15 @@ fn test_parse_digits() {
 
 #[test]
 fn test_a() {
-    assert_eq!(part_a(5), "0124515891");
-    assert_eq!(part_a(18), "9251071085");
-    assert_eq!(part_a(2018), "5941429882");
+    assert_eq!(part_a(5, &mut RunContext::silent()), "0124515891");
+    assert_eq!(part_a(18, &mut RunContext::silent()), "9251071085");
+    assert_eq!(part_a(2018, &mut RunContext::silent()), "5941429882");
 }
 
 #[test]
 fn test_b() {
-    assert_eq!(part_b("01245"), 5);
-    assert_eq!(part_b("51589"), 9);
-    assert_eq!(part_b("92510"), 18);
-    assert_eq!(part_b("59414"), 2018);
+    assert_eq!(part_b("01245", &mut RunContext::silent()), 5);
+    assert_eq!(part_b("51589", &mut RunContext::silent()), 9);
+    assert_eq!(part_b("92510", &mut RunContext::silent()), 18);
+    assert_eq!(part_b("59414", &mut RunContext::silent()), 2018);
 }
@@ -1,12 +1,59 @@
-use aoc::{buf_reader_from_arg, parse_lines};
+use aoc::input::{self, InputFormat, Record};
+use aoc::overflow::{Overflow, OverflowError};
+use aoc::parse::{parse_captures, Block, ParseError};
+use aoc::run::RunContext;
+use aoc::solve::solve_matching_with;
+use aoc::string_from_arg;
+use lazy_static::lazy_static;
 use regex::Regex;
-use std::collections::{BTreeMap, HashMap, HashSet};
-use std::ops::{Index, IndexMut};
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::ops::Index;
 use std::slice::Iter;
+use std::str::FromStr;
+
+type Instruction = (usize, usize, usize, usize);
+
+const NUM_OPCODES: usize = 16;
+const NUM_REGISTERS: usize = 4;
+
+lazy_static! {
+    static ref op_re: Regex = Regex::new(r"^(\d+) (\d+) (\d+) (\d+)$").unwrap();
+}
+
+/// Instructions fail when reading or writing a register that doesn't exist,
+/// which the register `a` or `b` of a sample may name for some opcodes
+#[derive(Debug, Eq, PartialEq)]
+enum EvalError {
+    Overflow(OverflowError),
+    InvalidRegister(usize),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::Overflow(error) => error.fmt(f),
+            EvalError::InvalidRegister(i) => write!(
+                f,
+                "Register {} does not exist, there are only {}",
+                i, NUM_REGISTERS
+            ),
+        }
+    }
+}
+
+impl Error for EvalError {}
+
+impl From<OverflowError> for EvalError {
+    fn from(error: OverflowError) -> Self {
+        EvalError::Overflow(error)
+    }
+}
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct Registers {
-    values: [usize; 4],
+    values: [usize; NUM_REGISTERS],
 }
 
 impl Registers {
@@ -15,6 +62,17 @@ impl Registers {
             values: [a, b, c, d],
         }
     }
+
+    fn get(&self, i: usize) -> Result<usize, EvalError> {
+        self.values
+            .get(i)
+            .copied()
+            .ok_or(EvalError::InvalidRegister(i))
+    }
+
+    fn get_mut(&mut self, i: usize) -> Result<&mut usize, EvalError> {
+        self.values.get_mut(i).ok_or(EvalError::InvalidRegister(i))
+    }
 }
 
 impl Index<usize> for Registers {
@@ -24,13 +82,7 @@ impl Index<usize> for Registers {
     }
 }
 
-impl IndexMut<usize> for Registers {
-    fn index_mut(&mut self, i: usize) -> &mut usize {
-        &mut self.values[i]
-    }
-}
-
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 enum OpCode {
     Addr,
     Addi,
@@ -52,82 +104,199 @@ enum OpCode {
 use self::OpCode::*;
 
 impl OpCode {
-    fn eval(&self, registers: &mut Registers, a: usize, b: usize, c: usize) {
-        match *self {
-            Addr => {
-                registers[c] = registers[a] + registers[b];
-            }
-            Addi => {
-                registers[c] = registers[a] + b;
-            }
-            Mulr => {
-                registers[c] = registers[a] * registers[b];
-            }
-            Muli => {
-                registers[c] = registers[a] * b;
-            }
-            Banr => {
-                registers[c] = registers[a] & registers[b];
-            }
-            Bani => {
-                registers[c] = registers[a] & b;
-            }
-            Borr => {
-                registers[c] = registers[a] | registers[b];
-            }
-            Bori => {
-                registers[c] = registers[a] | b;
-            }
-            Setr => {
-                registers[c] = registers[a];
-            }
-            Seti => {
-                registers[c] = a;
-            }
-            Gtir => {
-                registers[c] = (a > registers[b]) as usize;
-            }
-            Gtri => {
-                registers[c] = (registers[a] > b) as usize;
-            }
-            Gtrr => {
-                registers[c] = (registers[a] > registers[b]) as usize;
-            }
-            Eqir => {
-                registers[c] = (a == registers[b]) as usize;
-            }
-            Eqri => {
-                registers[c] = (registers[a] == b) as usize;
-            }
-            Eqrr => {
-                registers[c] = (registers[a] == registers[b]) as usize;
-            }
-        }
+    fn eval(
+        &self,
+        registers: &mut Registers,
+        a: usize,
+        b: usize,
+        c: usize,
+        overflow: Overflow,
+    ) -> Result<(), EvalError> {
+        let r = |i| registers.get(i);
+        let value = match *self {
+            Addr => overflow.add(r(a)?, r(b)?)?,
+            Addi => overflow.add(r(a)?, b)?,
+            Mulr => overflow.mul(r(a)?, r(b)?)?,
+            Muli => overflow.mul(r(a)?, b)?,
+            Banr => r(a)? & r(b)?,
+            Bani => r(a)? & b,
+            Borr => r(a)? | r(b)?,
+            Bori => r(a)? | b,
+            Setr => r(a)?,
+            Seti => a,
+            Gtir => (a > r(b)?) as usize,
+            Gtri => (r(a)? > b) as usize,
+            Gtrr => (r(a)? > r(b)?) as usize,
+            Eqir => (a == r(b)?) as usize,
+            Eqri => (r(a)? == b) as usize,
+            Eqrr => (r(a)? == r(b)?) as usize,
+        };
+        *registers.get_mut(c)? = value;
+        Ok(())
     }
 
     fn values() -> Iter<'static, Self> {
-        static OPCODES: [OpCode; 16] = [
+        static OPCODES: [OpCode; NUM_OPCODES] = [
             Addr, Addi, Mulr, Muli, Banr, Bani, Borr, Bori, Setr, Seti, Gtir, Gtri, Gtrr, Eqir,
             Eqri, Eqrr,
         ];
-        OPCODES.into_iter()
+        OPCODES.iter()
     }
 }
 
-fn eval_opcode(mut regs: Registers, opcode: OpCode, a: usize, b: usize, c: usize) -> Registers {
-    opcode.eval(&mut regs, a, b, c);
-    regs
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Sample {
+    before: Registers,
+    instruction: Instruction,
+    after: Registers,
 }
 
-fn part_a(
-    tests: impl Iterator<Item = (Registers, (usize, usize, usize, usize), Registers)>,
-) -> usize {
+impl FromStr for Sample {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        lazy_static! {
+            static ref reg_re: Regex =
+                Regex::new(r"^(?:Before:|After: ) \[(\d+), (\d+), (\d+), (\d+)\]$").unwrap();
+        }
+        let mut lines = s.lines();
+        let mut next_line = || lines.next().ok_or(ParseError::UnexpectedEnd);
+
+        let (a, b, c, d) = parse_captures(&reg_re, next_line()?)?;
+        let before = Registers::from_values(a, b, c, d);
+        let instruction = parse_instruction(next_line()?)?;
+        let (a, b, c, d) = parse_captures(&reg_re, next_line()?)?;
+        let after = Registers::from_values(a, b, c, d);
+
+        if let Some(line) = lines.next() {
+            return Err(ParseError::NoMatch(line.to_owned()));
+        }
+        Ok(Sample {
+            before,
+            instruction,
+            after,
+        })
+    }
+}
+
+/// Instruction with an opcode number that exists and a register `c` to write
+/// to. Whether `a` and `b` are registers depends on the opcode
+fn check_instruction(instruction: Instruction) -> Result<Instruction, ParseError> {
+    let (op, _, _, c) = instruction;
+    let (group, value) = if op >= NUM_OPCODES {
+        (1, op)
+    } else if c >= NUM_REGISTERS {
+        (4, c)
+    } else {
+        return Ok(instruction);
+    };
+    Err(ParseError::InvalidValue {
+        group,
+        value: value.to_string(),
+    })
+}
+
+fn parse_instruction(s: &str) -> Result<Instruction, ParseError> {
+    check_instruction(parse_captures(&op_re, s)?)
+}
+
+/// Four values from a list field, like `[3, 2, 1, 1]`
+fn quad(record: &Record, key: &str) -> Result<(usize, usize, usize, usize), ParseError> {
+    match record.get_list(key)?.as_slice() {
+        &[a, b, c, d] => Ok((a, b, c, d)),
+        values => Err(ParseError::InvalidField {
+            field: key.to_owned(),
+            value: format!("{:?}", values),
+        }),
+    }
+}
+
+/// Samples have the fields `before`, `instruction` and `after`. Records with
+/// only an `instruction` are the test program, in order
+fn parse_records(
+    input: &str,
+    format: InputFormat,
+) -> Result<(Vec<Sample>, Vec<Instruction>), ParseError> {
+    let mut tests = Vec::new();
+    let mut program = Vec::new();
+    for record in input::records(input, format)? {
+        let instruction = quad(&record, "instruction")
+            .and_then(|instruction| {
+                check_instruction(instruction).map_err(|_| ParseError::InvalidField {
+                    field: "instruction".to_owned(),
+                    value: format!("{:?}", instruction),
+                })
+            })
+            .map_err(|e| record.error(e))?;
+        if record.value("before").is_none() && record.value("after").is_none() {
+            program.push(instruction);
+            continue;
+        }
+
+        let registers = |key| {
+            let (a, b, c, d) = quad(&record, key).map_err(|e| record.error(e))?;
+            Ok::<_, ParseError>(Registers::from_values(a, b, c, d))
+        };
+        tests.push(Sample {
+            before: registers("before")?,
+            instruction,
+            after: registers("after")?,
+        });
+    }
+    Ok((tests, program))
+}
+
+fn parse_text(input: &str) -> Result<(Vec<Sample>, Vec<Instruction>), ParseError> {
+    // The samples are separated from the test program by more than one blank
+    // line
+    let sections = Block::new(input).sections(2);
+    let (samples, program) = match sections.as_slice() {
+        [samples, program] => (samples, program),
+        sections => {
+            return Err(ParseError::SectionCount {
+                expected: 2,
+                found: sections.len(),
+            })
+        }
+    };
+
+    let tests: Vec<Sample> = samples.parse_blocks()?;
+    let program = program
+        .lines()
+        .map(|line| parse_instruction(line.text).map_err(|e| line.error(e)))
+        .collect::<Result<Vec<Instruction>, _>>()?;
+    Ok((tests, program))
+}
+
+fn eval_opcode(
+    mut regs: Registers,
+    opcode: OpCode,
+    a: usize,
+    b: usize,
+    c: usize,
+    overflow: Overflow,
+) -> Result<Registers, EvalError> {
+    opcode.eval(&mut regs, a, b, c, overflow)?;
+    Ok(regs)
+}
+
+fn part_a(tests: &[Sample], overflow: Overflow) -> usize {
     let mut num_triples = 0;
-    for (in_, args, out) in tests {
+    for test in tests {
+        let args = test.instruction;
         let mut num_matching_opcodes = 0;
         for opcode in OpCode::values() {
-            let evaled_regs = eval_opcode(in_.clone(), *opcode, args.1, args.2, args.3);
-            if evaled_regs == out {
+            // Instructions that overflow or use a register that doesn't exist can't
+            // be the one that was sampled
+            let evaled_regs = eval_opcode(
+                test.before.clone(),
+                *opcode,
+                args.1,
+                args.2,
+                args.3,
+                overflow,
+            );
+            if evaled_regs.as_ref() == Ok(&test.after) {
                 num_matching_opcodes += 1;
             }
         }
@@ -139,131 +308,252 @@ fn part_a(
 }
 
 fn part_b(
-    tests: impl Iterator<Item = (Registers, (usize, usize, usize, usize), Registers)>,
-    program: impl Iterator<Item = (usize, usize, usize, usize)>,
-) -> usize {
-    let opcodes: HashSet<_> = OpCode::values().map(|x| x.clone()).collect();
+    tests: &[Sample],
+    program: &[Instruction],
+    ctx: &mut RunContext,
+) -> Result<usize, Box<dyn Error>> {
+    let overflow = ctx.overflow;
+    let opcodes: HashSet<_> = OpCode::values().copied().collect();
 
     let mut op_map: BTreeMap<usize, HashSet<OpCode>> = BTreeMap::new();
-    for i in 0..16 {
+    for i in 0..NUM_OPCODES {
         op_map.insert(i, opcodes.clone());
     }
 
-    for (in_, args, out) in tests {
+    for test in tests {
+        let args = test.instruction;
         for opcode in OpCode::values() {
-            let evaled_regs = eval_opcode(in_.clone(), *opcode, args.1, args.2, args.3);
-            if evaled_regs != out {
-                op_map.get_mut(&args.0).unwrap().remove(opcode);
+            let evaled_regs = eval_opcode(
+                test.before.clone(),
+                *opcode,
+                args.1,
+                args.2,
+                args.3,
+                overflow,
+            );
+            if evaled_regs.as_ref() != Ok(&test.after)
+                && op_map.get_mut(&args.0).unwrap().remove(opcode)
+            {
+                aoc::trace!(
+                    Debug,
+                    "eliminate",
+                    number = args.0,
+                    opcode = format!("{:?}", opcode),
+                );
             }
         }
     }
 
-    let mut done_opcodes: HashSet<OpCode> = HashSet::new();
-    while done_opcodes != opcodes {
-        done_opcodes.extend(
-            op_map
-                .values()
-                .filter(|x| x.len() == 1)
-                .map(|x| x.iter().next().unwrap()),
+    let opcodes = solve_matching_with(op_map, |number, opcode, remaining| {
+        aoc::trace!(
+            Info,
+            "eliminate",
+            number = *number,
+            opcode = format!("{:?}", opcode),
+            remaining = remaining,
+        );
+    })
+    .unique()?;
+    for (number, opcode) in opcodes.iter() {
+        aoc::trace!(
+            Info,
+            "resolve",
+            number = *number,
+            opcode = format!("{:?}", opcode),
         );
-
-        for (_, possible_ops) in op_map.range_mut(..) {
-            if possible_ops.len() == 1 {
-                continue;
-            }
-            for done_op in done_opcodes.iter() {
-                possible_ops.remove(&done_op);
-            }
-        }
     }
 
-    // Convert the opcodes to a true map
-    let opcodes: HashMap<usize, OpCode> = op_map
-        .into_iter()
-        .map(|(k, v)| (k, v.iter().next().unwrap().clone()))
-        .collect();
-
     let mut regs = Registers::from_values(0, 0, 0, 0);
-    for (op, a, b, c) in program {
-        opcodes[&op].eval(&mut regs, a, b, c);
+    for (ip, &(op, a, b, c)) in program.iter().enumerate() {
+        // Instructions are checked when parsing, and a unique matching covers
+        // every opcode number
+        let opcode = opcodes[&op];
+        opcode.eval(&mut regs, a, b, c, overflow)?;
+        aoc::record!(
+            ctx.recorder,
+            ip = ip,
+            opcode = format!("{:?}", opcode),
+            args = vec![a, b, c],
+            registers = regs.values.to_vec(),
+        );
     }
-    regs[0]
+    Ok(regs[0])
 }
 
-fn main() {
-    let reg_re = Regex::new(r"^(?:Before:|After: ) \[(\d+), (\d+), (\d+), (\d+)\]$").unwrap();
-    let op_re = Regex::new(r"^(\d+) (\d+) (\d+) (\d+)$").unwrap();
-    let mut tests = Vec::new();
-    let mut lines = parse_lines::<String>(buf_reader_from_arg().unwrap());
+fn main() -> Result<(), Box<dyn Error>> {
+    let input = string_from_arg()?;
+    let (tests, program) = match InputFormat::from_args(aoc::cli::args()) {
+        InputFormat::Text => parse_text(&input)?,
+        format => parse_records(&input, format)?,
+    };
 
-    // Parse the samples from the first half of the file
-    loop {
-        let in_line = lines.next().unwrap();
-        if in_line == "" {
-            break;
-        }
-        let op_line = lines.next().unwrap();
-        let out_line = lines.next().unwrap();
-
-        // Skip blank line
-        lines.next().unwrap();
-
-        let in_capt = reg_re.captures(&in_line).unwrap();
-        let in_ = Registers::from_values(
-            in_capt[1].parse::<usize>().unwrap(),
-            in_capt[2].parse::<usize>().unwrap(),
-            in_capt[3].parse::<usize>().unwrap(),
-            in_capt[4].parse::<usize>().unwrap(),
-        );
+    let mut ctx = RunContext::from_args(aoc::cli::args())?;
+    println!("Answer A: {}", part_a(&tests, ctx.overflow));
 
-        let op_capt = op_re.captures(&op_line).unwrap();
-        let op = (
-            op_capt[1].parse::<usize>().unwrap(),
-            op_capt[2].parse::<usize>().unwrap(),
-            op_capt[3].parse::<usize>().unwrap(),
-            op_capt[4].parse::<usize>().unwrap(),
-        );
+    // Only part B runs a program, so it's the only part with steps
+    ctx.part("B");
+    println!("Answer B: {}", part_b(&tests, &program, &mut ctx)?);
+    ctx.finish()?;
+    Ok(())
+}
 
-        let out_capt = reg_re.captures(&out_line).unwrap();
-        let out = Registers::from_values(
-            out_capt[1].parse::<usize>().unwrap(),
-            out_capt[2].parse::<usize>().unwrap(),
-            out_capt[3].parse::<usize>().unwrap(),
-            out_capt[4].parse::<usize>().unwrap(),
-        );
+#[test]
+fn test_parse_sample() {
+    assert_eq!(
+        "Before: [3, 2, 1, 1]\n9 2 1 2\nAfter:  [3, 2, 2, 1]".parse::<Sample>(),
+        Ok(Sample {
+            before: Registers::from_values(3, 2, 1, 1),
+            instruction: (9, 2, 1, 2),
+            after: Registers::from_values(3, 2, 2, 1),
+        })
+    );
+    assert_eq!(
+        "Before: [3, 2, 1, 1]\n9 2 1 2".parse::<Sample>(),
+        Err(ParseError::UnexpectedEnd)
+    );
+    assert_eq!(
+        "Before: [3, 2, 1, 1]\n16 2 1 2\nAfter:  [3, 2, 2, 1]".parse::<Sample>(),
+        Err(ParseError::InvalidValue {
+            group: 1,
+            value: "16".to_owned()
+        })
+    );
+    assert_eq!(
+        "Before: [3, 2, 1, 1]\n9 2 1 4\nAfter:  [3, 2, 2, 1]".parse::<Sample>(),
+        Err(ParseError::InvalidValue {
+            group: 4,
+            value: "4".to_owned()
+        })
+    );
+}
 
-        tests.push((in_, op, out));
-    }
+#[test]
+fn test_parse_invalid() {
+    assert_eq!(
+        parse_text("Before: [3, 2, 1, 1]\n9 2 1 2\nAfter:  [3, 2, 2, 1]\n"),
+        Err(ParseError::SectionCount {
+            expected: 2,
+            found: 1
+        })
+    );
+    assert_eq!(
+        parse_text("Before: [3, 2, 1, 1]\n9 2 1 2\nAfter:  [3, 2, 2, 1]\n\n\n\n7 3 2 9\n"),
+        Err(ParseError::AtLine {
+            line: 7,
+            error: Box::new(ParseError::InvalidValue {
+                group: 4,
+                value: "9".to_owned()
+            })
+        })
+    );
+    assert_eq!(
+        parse_records("instruction\n16 0 0 0\n", InputFormat::Csv),
+        Err(ParseError::AtLine {
+            line: 2,
+            error: Box::new(ParseError::InvalidField {
+                field: "instruction".to_owned(),
+                value: "(16, 0, 0, 0)".to_owned()
+            })
+        })
+    );
+}
 
-    // Skip remaining blank line before program input starts
-    lines.next();
-
-    let program = lines.map(move |x| {
-        let op_capt = op_re.captures(&x).unwrap();
-        (
-            op_capt[1].parse::<usize>().unwrap(),
-            op_capt[2].parse::<usize>().unwrap(),
-            op_capt[3].parse::<usize>().unwrap(),
-            op_capt[4].parse::<usize>().unwrap(),
-        )
-    });
-
-    println!("Answer A: {}", part_a(tests.clone().into_iter()));
-    println!("Answer B: {}", part_b(tests.into_iter(), program));
+#[test]
+fn test_parse_structured() {
+    let text = "Before: [3, 2, 1, 1]\n9 2 1 2\nAfter:  [3, 2, 2, 1]\n\n\n\n7 3 2 0\n";
+    let json = r#"[
+        {"before": [3, 2, 1, 1], "instruction": [9, 2, 1, 2], "after": [3, 2, 2, 1]},
+        {"instruction": [7, 3, 2, 0]}
+    ]"#;
+    let csv = "before,instruction,after\n3 2 1 1,9 2 1 2,3 2 2 1\n,7 3 2 0,\n";
+
+    let expected = parse_text(text).unwrap();
+    assert_eq!((expected.0.len(), expected.1.len()), (1, 1));
+    assert_eq!(
+        parse_records(json, InputFormat::Json).as_ref(),
+        Ok(&expected)
+    );
+    assert_eq!(parse_records(csv, InputFormat::Csv), Ok(expected));
+    assert_eq!(
+        parse_records(
+            r#"[{"before": [1], "instruction": [0, 0, 0, 0]}]"#,
+            InputFormat::Json
+        ),
+        Err(ParseError::AtRecord {
+            index: 1,
+            error: Box::new(ParseError::InvalidField {
+                field: "before".to_owned(),
+                value: "[1]".to_owned()
+            })
+        })
+    );
+    assert_eq!(
+        parse_records("before,instruction\n1 2 3 4,0 0 0 0\n", InputFormat::Csv),
+        Err(ParseError::AtLine {
+            line: 2,
+            error: Box::new(ParseError::MissingField("after".to_owned()))
+        })
+    );
 }
 
 #[test]
 fn test_opcode_eval() {
     assert_eq!(
-        eval_opcode(Registers::from_values(3, 2, 1, 1), Mulr, 2, 1, 2),
-        Registers::from_values(3, 2, 2, 1)
+        eval_opcode(
+            Registers::from_values(3, 2, 1, 1),
+            Mulr,
+            2,
+            1,
+            2,
+            Overflow::Checked
+        ),
+        Ok(Registers::from_values(3, 2, 2, 1))
+    );
+    assert_eq!(
+        eval_opcode(
+            Registers::from_values(3, 2, 1, 1),
+            Addi,
+            2,
+            1,
+            2,
+            Overflow::Checked
+        ),
+        Ok(Registers::from_values(3, 2, 2, 1))
     );
     assert_eq!(
-        eval_opcode(Registers::from_values(3, 2, 1, 1), Addi, 2, 1, 2),
-        Registers::from_values(3, 2, 2, 1)
+        eval_opcode(
+            Registers::from_values(3, 2, 1, 1),
+            Seti,
+            2,
+            1,
+            2,
+            Overflow::Checked
+        ),
+        Ok(Registers::from_values(3, 2, 2, 1))
+    );
+    assert_eq!(
+        eval_opcode(
+            Registers::from_values(3, 2, 1, 1),
+            Addr,
+            5,
+            1,
+            2,
+            Overflow::Checked
+        ),
+        Err(EvalError::InvalidRegister(5))
+    );
+}
+
+#[test]
+fn test_opcode_overflow() {
+    let regs = Registers::from_values(usize::MAX, 2, 0, 0);
+    assert!(eval_opcode(regs.clone(), Addr, 0, 1, 2, Overflow::Checked).is_err());
+    assert_eq!(
+        eval_opcode(regs.clone(), Addr, 0, 1, 2, Overflow::Wrapping),
+        Ok(Registers::from_values(usize::MAX, 2, 1, 0))
     );
     assert_eq!(
-        eval_opcode(Registers::from_values(3, 2, 1, 1), Seti, 2, 1, 2),
-        Registers::from_values(3, 2, 2, 1)
+        eval_opcode(regs, Muli, 0, 3, 2, Overflow::Saturating),
+        Ok(Registers::from_values(usize::MAX, 2, usize::MAX, 0))
     );
 }
@@ -1,5 +1,8 @@
-use aoc::{buf_reader_from_arg, parse_lines};
+use aoc::input::{self, InputFormat, Record};
+use aoc::parse::{parse_value, ParseError};
+use aoc::string_from_arg;
 use std::collections::HashMap;
+use std::error::Error;
 use std::str::FromStr;
 
 #[derive(Debug, PartialEq)]
@@ -37,6 +40,10 @@ impl FromStr for ChecksumPart {
     }
 }
 
+fn id_from_record(record: &Record) -> Result<String, ParseError> {
+    record.get("id")
+}
+
 fn part_a(parts: impl Iterator<Item = ChecksumPart>) -> usize {
     let mut doubles = 0;
     let mut triples = 0;
@@ -71,14 +78,33 @@ fn part_b(box_ids: impl Iterator<Item = String>) -> String {
     panic!("No similar boxes found");
 }
 
-fn main() {
-    println!(
-        "Answer A: {}",
-        part_a(parse_lines::<ChecksumPart>(buf_reader_from_arg().unwrap()))
+fn main() -> Result<(), Box<dyn Error>> {
+    let input = string_from_arg()?;
+    let format = InputFormat::from_args(aoc::cli::args());
+    let box_ids = input::parse_items(&input, format, parse_value, id_from_record)?;
+    let parts = box_ids.iter().map(|id| id.parse::<ChecksumPart>().unwrap());
+    println!("Answer A: {}", part_a(parts));
+    println!("Answer B: {}", part_b(box_ids.into_iter()));
+    Ok(())
+}
+
+#[test]
+fn test_parse_structured() {
+    let parse = |input, format| input::parse_items(input, format, parse_value, id_from_record);
+    let expected = Ok(vec!["abcde".to_owned(), "fghij".to_owned()]);
+
+    assert_eq!(parse("abcde\nfghij\n", InputFormat::Text), expected);
+    assert_eq!(
+        parse(r#"[{"id": "abcde"}, {"id": "fghij"}]"#, InputFormat::Json),
+        expected
     );
-    println!(
-        "Answer B: {}",
-        part_b(parse_lines::<String>(buf_reader_from_arg().unwrap()))
+    assert_eq!(parse("id\nabcde\nfghij\n", InputFormat::Csv), expected);
+    assert_eq!(
+        parse("id,note\nabcde\n,x\n", InputFormat::Csv),
+        Err(ParseError::AtLine {
+            line: 3,
+            error: Box::new(ParseError::MissingField("id".to_owned()))
+        })
     );
 }
 
@@ -1,124 +1,106 @@
-use aoc::{buf_reader_from_arg, parse_lines};
+use aoc::geom::{Point2, Rect};
+use aoc::grid::Grid;
+use aoc::image::{self, ColorMap};
+use aoc::input::{self, InputFormat, Record};
+use aoc::parse::{parse_captures, ParseError};
+use aoc::rects::RectSet;
+use aoc::string_from_arg;
 use lazy_static::lazy_static;
 use regex::Regex;
+use std::error::Error;
 use std::str::FromStr;
 
 #[derive(Debug, PartialEq, Eq)]
 struct Rectangle {
     id: usize,
-    x: usize,
-    y: usize,
-    width: usize,
-    height: usize,
+    area: Rect,
 }
 
 impl FromStr for Rectangle {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(rect: &str) -> Result<Self, Self::Err> {
         lazy_static! {
-            static ref rect_re: Regex = Regex::new(r"^#(\d+) @ (\d+),(\d+): (\d+)x(\d+)$").unwrap();
+            static ref rect_re: Regex = Regex::new(r"^#(\d+) @ (\d+,\d+): (\d+)x(\d+)$").unwrap();
+        }
+        let (id, pos, width, height): (usize, Point2, i64, i64) = parse_captures(&rect_re, rect)?;
+        for &(group, size) in &[(3, width), (4, height)] {
+            if size == 0 {
+                return Err(ParseError::InvalidValue {
+                    group,
+                    value: size.to_string(),
+                });
+            }
         }
-        let caps = rect_re.captures(rect).ok_or(())?;
-
-        let id = caps
-            .get(1)
-            .ok_or(())?
-            .as_str()
-            .parse::<usize>()
-            .or(Err(()))?;
-        let x = caps
-            .get(2)
-            .ok_or(())?
-            .as_str()
-            .parse::<usize>()
-            .or(Err(()))?;
-        let y = caps
-            .get(3)
-            .ok_or(())?
-            .as_str()
-            .parse::<usize>()
-            .or(Err(()))?;
-        let width = caps
-            .get(4)
-            .ok_or(())?
-            .as_str()
-            .parse::<usize>()
-            .or(Err(()))?;
-        let height = caps
-            .get(5)
-            .ok_or(())?
-            .as_str()
-            .parse::<usize>()
-            .or(Err(()))?;
-
         Ok(Rectangle {
             id,
-            x,
-            y,
-            width,
-            height,
+            area: Rect::from_size(pos, width, height),
         })
     }
 }
 
-struct Fabric {
-    tiles: [u8; 1_000_000],
-}
-
-impl Fabric {
-    const WIDTH: usize = 1_000;
-
-    fn new() -> Self {
-        Self {
-            tiles: [0; 1_000_000],
-        }
+impl Rectangle {
+    /// Claim with the fields `id`, `x`, `y`, `width` and `height`. Like in the
+    /// puzzle text, the position can't be negative and the size can't be zero
+    fn from_record(record: &Record) -> Result<Self, ParseError> {
+        let at_least = |key: &str, min: i64| match record.get::<i64>(key)? {
+            value if value < min => Err(ParseError::InvalidField {
+                field: key.to_owned(),
+                value: value.to_string(),
+            }),
+            value => Ok(value),
+        };
+        let pos = Point2::new(at_least("x", 0)?, at_least("y", 0)?);
+        Ok(Rectangle {
+            id: record.get("id")?,
+            area: Rect::from_size(pos, at_least("width", 1)?, at_least("height", 1)?),
+        })
     }
+}
 
-    fn alloc_patch(&mut self, rect: &Rectangle) -> u8 {
-        let mut num_overlaps = 0;
-        for x in rect.x..rect.width + rect.x {
-            for y in rect.y..rect.height + rect.y {
-                let tile = self.tiles.get_mut(x + y * Self::WIDTH).unwrap();
-
-                if *tile > num_overlaps {
-                    num_overlaps = *tile;
-                }
-
-                *tile += 1;
-            }
-        }
-        num_overlaps
-    }
+fn part_ab(rects: &[Rectangle]) -> (usize, usize) {
+    let set: RectSet = rects.iter().map(|r| r.area).collect();
+    let num_overlaps = set.overlap_area() as usize;
 
-    fn num_overlaps(&self) -> usize {
-        self.tiles.iter().filter(|t| **t > 1).count()
+    // The intact claim is the only one that isn't clustered with any other
+    match set.clusters().iter().find(|cluster| cluster.len() == 1) {
+        Some(cluster) => (num_overlaps, rects[cluster[0]].id),
+        None => panic!("Found no non-overlapping rectangles"),
     }
 }
 
-fn part_ab(rects: &Vec<Rectangle>) -> (usize, usize) {
-    let mut fabric = Fabric::new();
-    for rect in rects.iter() {
-        fabric.alloc_patch(rect);
-    }
-
-    let num_overlaps = fabric.num_overlaps();
-
-    for rect in rects.iter() {
-        if fabric.alloc_patch(rect) == 1 {
-            return (num_overlaps, rect.id);
+/// Number of claims that cover each square inch of the fabric within the
+/// bounding box of the claims, so `(0, 0)` is its top left corner
+fn claim_counts(rects: &[Rectangle]) -> Grid<u32> {
+    let bounds = rects
+        .iter()
+        .fold(Rect::default(), |bounds, r| bounds.hull(&r.area));
+    let mut counts = Grid::new(bounds.width() as usize, bounds.height() as usize);
+    for rect in rects {
+        for p in rect.area.points() {
+            let p = p - bounds.min;
+            counts[(p.x as usize, p.y as usize)] += 1;
         }
     }
-
-    panic!("Found no non-overlapping rectangles");
+    counts
 }
 
-fn main() {
-    let rects =
-        parse_lines::<Rectangle>(buf_reader_from_arg().unwrap()).collect::<Vec<Rectangle>>();
+fn main() -> Result<(), Box<dyn Error>> {
+    let input = string_from_arg()?;
+    let format = InputFormat::from_args(aoc::cli::args());
+    let rects = input::parse_items(&input, format, str::parse, Rectangle::from_record)?;
     let (a, b) = part_ab(&rects);
     println!("Answer A: {}", a);
     println!("Answer B: {}", b);
+
+    if let Some(path) = &aoc::cli::args().image {
+        let counts = claim_counts(&rects);
+        image::save(
+            &image::heatmap(&counts, ColorMap::Heat, |&c| c as f64),
+            path,
+        )?;
+    }
+    Ok(())
 }
 
 #[test]
@@ -127,30 +109,78 @@ fn test_parse_rectangle() {
         "#1 @ 1,3: 4x4".parse::<Rectangle>(),
         Ok(Rectangle {
             id: 1,
-            x: 1,
-            y: 3,
-            width: 4,
-            height: 4
+            area: Rect::from_size(Point2::new(1, 3), 4, 4),
         })
     );
     assert_eq!(
         "#2 @ 3,1: 4x4".parse::<Rectangle>(),
         Ok(Rectangle {
             id: 2,
-            x: 3,
-            y: 1,
-            width: 4,
-            height: 4
+            area: Rect::from_size(Point2::new(3, 1), 4, 4),
         })
     );
     assert_eq!(
         "#3 @ 5,5: 2x2".parse::<Rectangle>(),
         Ok(Rectangle {
             id: 3,
-            x: 5,
-            y: 5,
-            width: 2,
-            height: 2
+            area: Rect::from_size(Point2::new(5, 5), 2, 2),
+        })
+    );
+}
+
+#[test]
+fn test_parse_structured() {
+    let text = "#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n";
+    let json = r#"[
+        {"id": 1, "x": 1, "y": 3, "width": 4, "height": 4},
+        {"id": 2, "x": 3, "y": 1, "width": 4, "height": 4}
+    ]"#;
+    let csv = "id,x,y,width,height\n1,1,3,4,4\n2,3,1,4,4\n";
+
+    let parse =
+        |input, format| input::parse_items(input, format, str::parse, Rectangle::from_record);
+    let expected = parse(text, InputFormat::Text).unwrap();
+    assert_eq!(expected.len(), 2);
+    assert_eq!(parse(json, InputFormat::Json).as_ref(), Ok(&expected));
+    assert_eq!(parse(csv, InputFormat::Csv), Ok(expected));
+    assert_eq!(
+        parse("id,x,y,width\n1,1,3,4\n", InputFormat::Csv),
+        Err(ParseError::AtLine {
+            line: 2,
+            error: Box::new(ParseError::MissingField("height".to_owned()))
+        })
+    );
+    assert_eq!(
+        parse(
+            r#"[{"id": 1, "x": -1, "y": 3, "width": 4, "height": 4}]"#,
+            InputFormat::Json
+        ),
+        Err(ParseError::AtRecord {
+            index: 1,
+            error: Box::new(ParseError::InvalidField {
+                field: "x".to_owned(),
+                value: "-1".to_owned()
+            })
+        })
+    );
+    assert_eq!(
+        parse("id,x,y,width,height\n1,1,3,0,4\n", InputFormat::Csv),
+        Err(ParseError::AtLine {
+            line: 2,
+            error: Box::new(ParseError::InvalidField {
+                field: "width".to_owned(),
+                value: "0".to_owned()
+            })
+        })
+    );
+    assert_eq!(
+        parse("#1 @ 1,3: 4x0\n", InputFormat::Text),
+        Err(ParseError::AtLine {
+            line: 1,
+            error: Box::new(ParseError::InvalidValue {
+                group: 4,
+                value: "0".to_owned()
+            })
         })
     );
 }
@@ -164,3 +194,17 @@ fn test_part_ab() {
     ];
     assert_eq!(part_ab(&data), (4, 3));
 }
+
+#[test]
+fn test_claim_counts() {
+    let data = vec![
+        "#1 @ 1,3: 4x4".parse::<Rectangle>().unwrap(),
+        "#2 @ 3,1: 4x4".parse::<Rectangle>().unwrap(),
+    ];
+    let counts = claim_counts(&data);
+    assert_eq!((counts.width(), counts.height()), (6, 6));
+    assert_eq!(counts.iter().filter(|&&c| c == 2).count(), 4);
+    assert_eq!(counts[(0, 0)], 0);
+    assert_eq!(counts[(0, 2)], 1);
+    assert_eq!(counts[(2, 2)], 2);
+}
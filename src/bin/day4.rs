@@ -1,53 +1,70 @@
-use aoc::{buf_reader_from_arg, parse_lines};
+use aoc::input::{self, InputFormat, Record};
+use aoc::parse::{parse_captures, ParseError};
+use aoc::ranges::RangeSet;
+use aoc::string_from_arg;
 use chrono::{NaiveDateTime, Timelike};
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::HashMap;
+use std::error::Error;
 use std::num::NonZeroUsize;
 use std::str::FromStr;
 
 struct SleepPattern {
-    by_min: [usize; 60],
+    /// Minutes the guard has been asleep more than `i` times are in `asleep[i]`
+    asleep: Vec<RangeSet<usize>>,
     sleep_start: Option<usize>,
-    num_mins: usize,
 }
 
 impl SleepPattern {
     fn new() -> Self {
         Self {
-            by_min: [0; 60],
+            asleep: Vec::new(),
             sleep_start: None,
-            num_mins: 0,
         }
     }
-    fn go_to_sleep(&mut self, min: usize) {
+
+    /// False if the guard is already asleep
+    fn go_to_sleep(&mut self, min: usize) -> bool {
         if self.sleep_start.is_some() {
-            panic!("Guard is already asleep");
+            return false;
         }
         self.sleep_start = Some(min);
+        true
     }
 
-    fn wake_up(&mut self, min: usize) {
-        if self.sleep_start.is_none() {
-            panic!("Guard is not asleep");
+    /// False if the guard is not asleep
+    fn wake_up(&mut self, min: usize) -> bool {
+        let start = match self.sleep_start.take() {
+            Some(start) => start,
+            None => return false,
+        };
+
+        // Minutes that were already in a layer move on to the next one
+        let mut nap: RangeSet<usize> = std::iter::once(start..min).collect();
+        for layer in self.asleep.iter_mut() {
+            if nap.is_empty() {
+                return true;
+            }
+            let again = nap.intersection(layer);
+            layer.extend(nap.iter());
+            nap = again;
         }
-        for min in self.sleep_start.unwrap()..min {
-            self.by_min[min] += 1;
-            self.num_mins += 1;
+        if !nap.is_empty() {
+            self.asleep.push(nap);
         }
-        self.sleep_start = None;
+        true
     }
 
-    fn most_asleep(&self) -> Option<(usize, usize)> {
-        if self.num_mins == 0 {
-            return None;
-        }
+    fn num_mins(&self) -> usize {
+        self.asleep.iter().map(RangeSet::coverage).sum()
+    }
 
-        self.by_min
-            .iter()
-            .cloned()
-            .enumerate()
-            .max_by_key(|(_, count)| *count)
+    /// The minute the guard is most often asleep, and how many times. Ties go
+    /// to the latest minute
+    fn most_asleep(&self) -> Option<(usize, usize)> {
+        let last_min = self.asleep.last()?.iter().last()?.end - 1;
+        Some((last_min, self.asleep.len()))
     }
 }
 
@@ -65,7 +82,7 @@ struct GuardEvent {
 }
 
 impl FromStr for GuardEvent {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         lazy_static! {
@@ -75,16 +92,27 @@ impl FromStr for GuardEvent {
             .unwrap();
         }
 
-        let captures = guard_re.captures(s).ok_or(())?;
-        let ts = NaiveDateTime::parse_from_str(captures.get(1).unwrap().as_str(), "%Y-%m-%d %H:%M")
-            .unwrap();
+        let (ts, id, asleep): (String, Option<usize>, Option<String>) =
+            parse_captures(&guard_re, s)?;
+        let ts = NaiveDateTime::parse_from_str(&ts, "%Y-%m-%d %H:%M").map_err(|_| {
+            ParseError::InvalidValue {
+                group: 1,
+                value: ts.clone(),
+            }
+        })?;
 
-        if let Some(string_id) = captures.get(2) {
+        if id == Some(0) {
+            return Err(ParseError::InvalidValue {
+                group: 2,
+                value: "0".to_owned(),
+            });
+        }
+        if let Some(id) = id {
             Ok(Self {
                 timestamp: ts,
-                event_type: GuardEventType::Begin(string_id.as_str().parse::<usize>().or(Err(()))?),
+                event_type: GuardEventType::Begin(id),
             })
-        } else if captures.get(3) != None {
+        } else if asleep.is_some() {
             Ok(Self {
                 timestamp: ts,
                 event_type: GuardEventType::Asleep,
@@ -98,12 +126,80 @@ impl FromStr for GuardEvent {
     }
 }
 
+impl GuardEvent {
+    /// Event with a `timestamp` like `1518-11-01 00:05` and an `event` that
+    /// is `begin`, `asleep` or `awake`. Beginning a shift requires a `guard`
+    /// other than 0
+    fn from_record(record: &Record) -> Result<Self, ParseError> {
+        let ts: String = record.get("timestamp")?;
+        let timestamp = NaiveDateTime::parse_from_str(&ts, "%Y-%m-%d %H:%M").map_err(|_| {
+            ParseError::InvalidField {
+                field: "timestamp".to_owned(),
+                value: ts.clone(),
+            }
+        })?;
+
+        let event: String = record.get("event")?;
+        let event_type = match event.as_str() {
+            "begin" => GuardEventType::Begin(record.get::<NonZeroUsize>("guard")?.get()),
+            "asleep" => GuardEventType::Asleep,
+            "awake" => GuardEventType::Awake,
+            _ => {
+                return Err(ParseError::InvalidField {
+                    field: "event".to_owned(),
+                    value: event,
+                })
+            }
+        };
+        Ok(Self {
+            timestamp,
+            event_type,
+        })
+    }
+
+    /// Error for an event that can't happen at this point
+    fn unexpected(&self) -> ParseError {
+        ParseError::NoMatch(format!("{} {:?}", self.timestamp, self.event_type))
+    }
+}
+
+/// Sleep pattern of each guard from events sorted by time. Every event must
+/// belong to a shift, and guards must fall asleep and wake up in turn
+fn sleep_patterns(events: Vec<GuardEvent>) -> Result<HashMap<usize, SleepPattern>, ParseError> {
+    let mut guard_patterns = HashMap::new();
+    let mut current_id = None;
+    for event in events {
+        if let GuardEventType::Begin(id) = event.event_type {
+            current_id = Some(id);
+        }
+        let id = current_id.ok_or_else(|| event.unexpected())?;
+        aoc::trace!(
+            Info,
+            "guard_event",
+            guard = id,
+            timestamp = event.timestamp.to_string(),
+            event = format!("{:?}", event.event_type),
+        );
+        let entry = guard_patterns.entry(id).or_insert_with(SleepPattern::new);
+        let min = event.timestamp.minute() as usize;
+        let valid = match event.event_type {
+            GuardEventType::Begin(_) => true,
+            GuardEventType::Asleep => entry.go_to_sleep(min),
+            GuardEventType::Awake => entry.wake_up(min),
+        };
+        if !valid {
+            return Err(event.unexpected());
+        }
+    }
+    Ok(guard_patterns)
+}
+
 fn part_a(guard_patterns: &HashMap<usize, SleepPattern>) -> usize {
     let (id, _, min) = guard_patterns
         .iter()
         .map(|(k, v)| {
             let most_asleep = v.most_asleep().unwrap_or((0, 0));
-            (k, v.num_mins, most_asleep.0)
+            (k, v.num_mins(), most_asleep.0)
         })
         .max_by_key(|(_, num_mins, _)| *num_mins)
         .unwrap();
@@ -124,36 +220,16 @@ fn part_b(guard_patterns: &HashMap<usize, SleepPattern>) -> usize {
     id * min
 }
 
-fn main() {
-    let mut guard_events =
-        parse_lines::<String>(buf_reader_from_arg().unwrap()).collect::<Vec<String>>();
-    guard_events.sort();
-
-    let current_id: Option<NonZeroUsize> = None;
-    let it = guard_events
-        .into_iter()
-        .map(|e| e.parse::<GuardEvent>().unwrap())
-        .scan(current_id, |current_id, event| {
-            if let GuardEventType::Begin(id) = event.event_type {
-                *current_id = NonZeroUsize::new(id);
-            }
-            Some((*current_id, event))
-        });
-
-    let mut guard_patterns = HashMap::new();
-    for (id, event) in it {
-        let entry = guard_patterns
-            .entry(id.unwrap().get())
-            .or_insert(SleepPattern::new());
-        match event.event_type {
-            GuardEventType::Begin(_) => continue,
-            GuardEventType::Asleep => entry.go_to_sleep(event.timestamp.minute() as usize),
-            GuardEventType::Awake => entry.wake_up(event.timestamp.minute() as usize),
-        }
-    }
+fn main() -> Result<(), Box<dyn Error>> {
+    let input = string_from_arg()?;
+    let format = InputFormat::from_args(aoc::cli::args());
+    let mut guard_events = input::parse_items(&input, format, str::parse, GuardEvent::from_record)?;
+    guard_events.sort_by_key(|e| e.timestamp);
+    let guard_patterns = sleep_patterns(guard_events)?;
 
     println!("Answer A: {}", part_a(&guard_patterns));
     println!("Answer B: {}", part_b(&guard_patterns));
+    Ok(())
 }
 
 #[test]
@@ -162,22 +238,147 @@ fn test_parse_guard_event() {
     assert_eq!(
         "[1518-11-01 00:00] Guard #10 begins shift".parse::<GuardEvent>(),
         Ok(GuardEvent {
-            timestamp: NaiveDate::from_ymd(1518, 11, 1).and_hms(0, 0, 0),
+            timestamp: NaiveDate::from_ymd_opt(1518, 11, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
             event_type: GuardEventType::Begin(10)
         }),
     );
     assert_eq!(
         "[1518-11-01 00:05] falls asleep".parse::<GuardEvent>(),
         Ok(GuardEvent {
-            timestamp: NaiveDate::from_ymd(1518, 11, 1).and_hms(0, 5, 0),
+            timestamp: NaiveDate::from_ymd_opt(1518, 11, 1)
+                .unwrap()
+                .and_hms_opt(0, 5, 0)
+                .unwrap(),
             event_type: GuardEventType::Asleep
         }),
     );
     assert_eq!(
         "[1518-11-01 00:25] wakes up".parse::<GuardEvent>(),
         Ok(GuardEvent {
-            timestamp: NaiveDate::from_ymd(1518, 11, 1).and_hms(0, 25, 0),
+            timestamp: NaiveDate::from_ymd_opt(1518, 11, 1)
+                .unwrap()
+                .and_hms_opt(0, 25, 0)
+                .unwrap(),
             event_type: GuardEventType::Awake
         }),
     );
 }
+
+#[test]
+fn test_sleep_pattern() {
+    let mut pattern = SleepPattern::new();
+    assert_eq!(pattern.most_asleep(), None);
+
+    assert!(pattern.go_to_sleep(5));
+    assert!(pattern.wake_up(25));
+    assert!(pattern.go_to_sleep(30));
+    assert!(!pattern.go_to_sleep(31));
+    assert!(pattern.wake_up(55));
+    assert!(!pattern.wake_up(56));
+    assert!(pattern.go_to_sleep(24));
+    assert!(pattern.wake_up(29));
+    assert_eq!(pattern.num_mins(), 50);
+    assert_eq!(pattern.most_asleep(), Some((24, 2)));
+
+    let mut tied = SleepPattern::new();
+    assert!(tied.go_to_sleep(5));
+    assert!(tied.wake_up(10));
+    assert!(tied.go_to_sleep(40));
+    assert!(tied.wake_up(42));
+    assert_eq!(tied.num_mins(), 7);
+    assert_eq!(tied.most_asleep(), Some((41, 1)));
+}
+
+#[test]
+fn test_parse_structured() {
+    let text = "[1518-11-01 00:00] Guard #10 begins shift\n[1518-11-01 00:05] falls asleep\n";
+    let json = r#"[
+        {"timestamp": "1518-11-01 00:00", "event": "begin", "guard": 10},
+        {"timestamp": "1518-11-01 00:05", "event": "asleep"}
+    ]"#;
+    let csv = "timestamp,event,guard\n1518-11-01 00:00,begin,10\n1518-11-01 00:05,asleep,\n";
+
+    let parse =
+        |input, format| input::parse_items(input, format, str::parse, GuardEvent::from_record);
+    let expected = parse(text, InputFormat::Text).unwrap();
+    assert_eq!(parse(json, InputFormat::Json).as_ref(), Ok(&expected));
+    assert_eq!(parse(csv, InputFormat::Csv), Ok(expected));
+    assert_eq!(
+        parse(
+            "timestamp,event\n1518-11-01 00:00,begin\n",
+            InputFormat::Csv
+        ),
+        Err(ParseError::AtLine {
+            line: 2,
+            error: Box::new(ParseError::MissingField("guard".to_owned()))
+        })
+    );
+    assert_eq!(
+        parse(
+            r#"[{"timestamp": "1518-11-01", "event": "awake"}]"#,
+            InputFormat::Json
+        ),
+        Err(ParseError::AtRecord {
+            index: 1,
+            error: Box::new(ParseError::InvalidField {
+                field: "timestamp".to_owned(),
+                value: "1518-11-01".to_owned()
+            })
+        })
+    );
+    assert_eq!(
+        parse(
+            r#"[{"timestamp": "1518-11-01 00:00", "event": "begin", "guard": 0}]"#,
+            InputFormat::Json
+        ),
+        Err(ParseError::AtRecord {
+            index: 1,
+            error: Box::new(ParseError::InvalidField {
+                field: "guard".to_owned(),
+                value: "0".to_owned()
+            })
+        })
+    );
+    assert_eq!(
+        parse(
+            "[1518-11-01 00:00] Guard #0 begins shift",
+            InputFormat::Text
+        ),
+        Err(ParseError::AtLine {
+            line: 1,
+            error: Box::new(ParseError::InvalidValue {
+                group: 2,
+                value: "0".to_owned()
+            })
+        })
+    );
+}
+
+#[test]
+fn test_sleep_patterns() {
+    let parse = |text: &str| {
+        let events = aoc::parse::Block::new(text)
+            .parse_lines::<GuardEvent>()
+            .unwrap();
+        sleep_patterns(events)
+    };
+    let patterns = parse(
+        "[1518-11-01 00:00] Guard #10 begins shift\n\
+         [1518-11-01 00:05] falls asleep\n\
+         [1518-11-01 00:25] wakes up\n",
+    )
+    .unwrap();
+    assert_eq!(patterns[&10].num_mins(), 20);
+
+    assert_eq!(
+        parse("[1518-11-01 00:05] falls asleep\n").err(),
+        Some(ParseError::NoMatch("1518-11-01 00:05:00 Asleep".to_owned()))
+    );
+    assert_eq!(
+        parse("[1518-11-01 00:00] Guard #10 begins shift\n[1518-11-01 00:25] wakes up\n").err(),
+        Some(ParseError::NoMatch("1518-11-01 00:25:00 Awake".to_owned()))
+    );
+}
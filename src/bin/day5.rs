@@ -1,4 +1,15 @@
-use aoc::{buf_reader_from_arg, parse_lines};
+use aoc::input::{self, InputFormat, Record};
+use aoc::parse::{parse_value, ParseError};
+use aoc::string_from_arg;
+use std::error::Error;
+
+/// The polymer may be split across several lines or records
+fn parse_polymer(input: &str, format: InputFormat) -> Result<String, ParseError> {
+    let parts = input::parse_items(input, format, parse_value, |record: &Record| {
+        record.get::<String>("polymer")
+    })?;
+    Ok(parts.concat())
+}
 
 fn can_react(a: char, b: char) -> bool {
     match (a.is_uppercase(), b.is_uppercase()) {
@@ -33,12 +44,39 @@ fn part_b(polymer: String) -> usize {
         .unwrap()
 }
 
-fn main() {
-    let polymer = parse_lines::<String>(buf_reader_from_arg().unwrap())
-        .next()
-        .unwrap();
+fn main() -> Result<(), Box<dyn Error>> {
+    let input = string_from_arg()?;
+    let polymer = parse_polymer(&input, InputFormat::from_args(aoc::cli::args()))?;
     println!("Answer A: {}", part_a(polymer.chars()));
     println!("Answer B: {}", part_b(polymer));
+    Ok(())
+}
+
+#[test]
+fn test_parse_structured() {
+    let expected = Ok("dabAcCaCBAcCcaDA".to_owned());
+    assert_eq!(
+        parse_polymer("dabAcCaCBAcCcaDA\n", InputFormat::Text),
+        expected
+    );
+    assert_eq!(
+        parse_polymer(
+            r#"[{"polymer": "dabAcCaC"}, {"polymer": "BAcCcaDA"}]"#,
+            InputFormat::Json
+        ),
+        expected
+    );
+    assert_eq!(
+        parse_polymer("polymer\ndabAcCaCBAcCcaDA\n", InputFormat::Csv),
+        expected
+    );
+    assert_eq!(
+        parse_polymer("[{}]", InputFormat::Json),
+        Err(ParseError::AtRecord {
+            index: 1,
+            error: Box::new(ParseError::MissingField("polymer".to_owned()))
+        })
+    );
 }
 
 #[test]
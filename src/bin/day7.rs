@@ -1,78 +1,199 @@
-use aoc::{buf_reader_from_arg, parse_lines};
+use aoc::graph::Graph;
+use aoc::input::{self, InputFormat, Record};
+use aoc::parse::{parse_captures, ParseError};
+use aoc::sim::Simulation;
+use aoc::string_from_arg;
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
 
-fn parse_dep(s: String) -> (char, char) {
+fn parse_dep(s: &str) -> Result<(char, char), ParseError> {
     lazy_static! {
         static ref guard_re: Regex =
-            Regex::new(r"^Step (\w) must be finished before step (\w)").unwrap();
+            Regex::new(r"^Step ([A-Z]) must be finished before step ([A-Z])").unwrap();
     }
-    let captures = guard_re.captures(&s).unwrap();
-    (
-        captures[1].parse::<char>().unwrap(),
-        captures[2].parse::<char>().unwrap(),
-    )
+    parse_captures(&guard_re, s)
 }
 
-fn part_a(deps: impl Iterator<Item = (char, char)>) -> String {
-    let mut available = BTreeSet::new();
-    let mut dep_to_steps = BTreeMap::new();
-    let mut step_to_deps = BTreeMap::new();
+/// Steps are named by the letters `A` to `Z`, which also set how long they take
+fn step_from_record(record: &Record, key: &str) -> Result<char, ParseError> {
+    match record.get(key)? {
+        step @ 'A'..='Z' => Ok(step),
+        step => Err(ParseError::InvalidField {
+            field: key.to_owned(),
+            value: step.to_string(),
+        }),
+    }
+}
 
+/// Dependency where the step in `before` must be finished before the step in
+/// `after` can begin
+fn dep_from_record(record: &Record) -> Result<(char, char), ParseError> {
+    Ok((
+        step_from_record(record, "before")?,
+        step_from_record(record, "after")?,
+    ))
+}
+
+fn part_a(deps: impl Iterator<Item = (char, char)>) -> String {
+    let mut graph = Graph::new();
     for (dep, step) in deps {
-        dep_to_steps
-            .entry(dep)
-            .or_insert(BTreeSet::new())
-            .insert(step);
-        step_to_deps
-            .entry(step)
-            .or_insert(BTreeSet::new())
-            .insert(dep);
-
-        // Mark all dependencies as candidates for being available
-        available.insert(dep);
+        graph.add_edge(dep, step);
     }
 
-    // Only dependencies which have no dependencies themselves are available
-    // at the start
-    for step in step_to_deps.keys() {
-        available.remove(step);
+    // The lowest available letter is always done first, which is exactly a
+    // topological sort where ties are broken alphabetically
+    graph
+        .topological_sort()
+        .expect("Steps must not depend on each other in a cycle")
+        .into_iter()
+        .collect()
+}
+
+#[derive(Debug)]
+enum Event {
+    /// Give available steps to idle workers
+    Assign,
+    Finish(char),
+}
+
+struct Workshop {
+    graph: Graph<char>,
+    waiting_for: HashMap<char, usize>,
+    available: BTreeSet<char>,
+    idle_workers: usize,
+}
+
+fn part_b(deps: impl Iterator<Item = (char, char)>, num_workers: usize, base_time: u64) -> u64 {
+    let mut graph = Graph::new();
+    for (dep, step) in deps {
+        graph.add_edge(dep, step);
     }
+    let waiting_for: HashMap<char, usize> = graph
+        .nodes()
+        .map(|&step| (step, graph.predecessors(&step).count()))
+        .collect();
+    let available = waiting_for
+        .iter()
+        .filter(|(_, &num_deps)| num_deps == 0)
+        .map(|(&step, _)| step)
+        .collect();
+
+    let mut sim = Simulation::new(Workshop {
+        graph,
+        waiting_for,
+        available,
+        idle_workers: num_workers,
+    });
+
+    // Assignments are scheduled without delay after each finished step. Since
+    // events at the same time run in the order they were scheduled, all steps
+    // that finish at the same time are done before any new work is handed out
+    sim.schedule(0, Event::Assign);
+    sim.run(|sim, event| match event {
+        Event::Assign => {
+            while sim.state.idle_workers > 0 {
+                let step = match sim.state.available.iter().next() {
+                    Some(&step) => step,
+                    None => break,
+                };
+                sim.state.available.remove(&step);
+                sim.state.idle_workers -= 1;
 
-    let mut done = BTreeSet::new();
-    let mut out = String::with_capacity(step_to_deps.len());
-    while !available.is_empty() {
-        // Take the lowest available letter and put it in the output
-        let current = available.iter().next().unwrap().clone();
-        available.remove(&current);
-        out.push(current);
-        done.insert(current);
-
-        for step in dep_to_steps
-            .entry(current)
-            .or_insert(BTreeSet::new())
-            .iter()
-        {
-            if step_to_deps[step].is_subset(&done) {
-                available.insert(step.clone());
+                let duration = base_time + (step as u64 - 'A' as u64 + 1);
+                aoc::trace!(Info, "start", time = sim.now(), step = step);
+                sim.schedule(duration, Event::Finish(step));
             }
         }
-    }
+        Event::Finish(step) => {
+            aoc::trace!(Info, "finish", time = sim.now(), step = step);
+            let workshop = &mut sim.state;
+            workshop.idle_workers += 1;
+            for next in workshop.graph.successors(&step) {
+                let num_deps = workshop.waiting_for.get_mut(next).unwrap();
+                *num_deps -= 1;
+                if *num_deps == 0 {
+                    workshop.available.insert(*next);
+                }
+            }
+            sim.schedule(0, Event::Assign);
+        }
+    });
 
-    out
+    assert!(
+        sim.state.waiting_for.values().all(|&n| n == 0),
+        "Steps must not depend on each other in a cycle"
+    );
+    sim.now()
 }
 
-fn main() {
-    let deps = parse_lines::<String>(buf_reader_from_arg().unwrap()).map(parse_dep);
-    println!("Answer A: {}", part_a(deps));
+fn main() -> Result<(), Box<dyn Error>> {
+    let input = string_from_arg()?;
+    let format = InputFormat::from_args(aoc::cli::args());
+    let deps = input::parse_items(&input, format, parse_dep, dep_from_record)?;
+    println!("Answer A: {}", part_a(deps.iter().cloned()));
+    println!("Answer B: {}", part_b(deps.into_iter(), 5, 60));
+    Ok(())
 }
 
 #[test]
 fn test_parse_dep() {
     assert_eq!(
-        parse_dep("Step C must be finished before step A can begin.".into()),
-        ('C', 'A')
+        parse_dep("Step C must be finished before step A can begin."),
+        Ok(('C', 'A'))
+    );
+    assert_eq!(
+        parse_dep("Step c must be finished before step A can begin."),
+        Err(ParseError::NoMatch(
+            "Step c must be finished before step A can begin.".to_owned()
+        ))
+    );
+}
+
+#[test]
+fn test_parse_structured() {
+    let json = r#"[{"before": "C", "after": "A"}, {"before": "C", "after": "F"}]"#;
+    let csv = "before,after\nC,A\nC,F\n";
+    let expected = Ok(vec![('C', 'A'), ('C', 'F')]);
+
+    assert_eq!(
+        input::parse_items(json, InputFormat::Json, parse_dep, dep_from_record),
+        expected
+    );
+    assert_eq!(
+        input::parse_items(csv, InputFormat::Csv, parse_dep, dep_from_record),
+        expected
+    );
+    assert_eq!(
+        input::parse_items(
+            "before,after\nCD,A\n",
+            InputFormat::Csv,
+            parse_dep,
+            dep_from_record
+        ),
+        Err(ParseError::AtLine {
+            line: 2,
+            error: Box::new(ParseError::InvalidField {
+                field: "before".to_owned(),
+                value: "CD".to_owned()
+            })
+        })
+    );
+    assert_eq!(
+        input::parse_items(
+            r#"[{"before": "C", "after": "1"}]"#,
+            InputFormat::Json,
+            parse_dep,
+            dep_from_record
+        ),
+        Err(ParseError::AtRecord {
+            index: 1,
+            error: Box::new(ParseError::InvalidField {
+                field: "after".to_owned(),
+                value: "1".to_owned()
+            })
+        })
     );
 }
 
@@ -89,3 +210,17 @@ fn test_part_a() {
     ];
     assert_eq!(part_a(data.into_iter()), "CABDFE");
 }
+
+#[test]
+fn test_part_b() {
+    let data = vec![
+        ('C', 'A'),
+        ('C', 'F'),
+        ('A', 'B'),
+        ('A', 'D'),
+        ('B', 'E'),
+        ('D', 'E'),
+        ('F', 'E'),
+    ];
+    assert_eq!(part_b(data.into_iter(), 2, 0), 15);
+}
@@ -1,5 +1,20 @@
-use aoc::{buf_reader_from_arg, parse_lines};
+use aoc::input::{self, InputFormat, Record};
+use aoc::parse::{parse_value, ParseError};
+use aoc::string_from_arg;
 use std::collections::BTreeMap;
+use std::error::Error;
+
+/// Numbers of the license separated by whitespace, which may be split across
+/// several lines or records
+fn parse_license(input: &str, format: InputFormat) -> Result<Vec<u8>, ParseError> {
+    let parts = input::parse_items(
+        input,
+        format,
+        |line| line.split_whitespace().map(parse_value).collect(),
+        |record: &Record| record.get_list("license"),
+    )?;
+    Ok(parts.concat())
+}
 
 fn part_a(mut parts: impl Iterator<Item = u8>) -> usize {
     fn rec(parts: &mut impl Iterator<Item = u8>) -> usize {
@@ -48,14 +63,31 @@ fn part_b(mut parts: impl Iterator<Item = u8>) -> usize {
     rec(&mut parts)
 }
 
-fn main() {
-    let line = parse_lines::<String>(buf_reader_from_arg().unwrap())
-        .next()
-        .unwrap();
-    let license = line.split(" ").map(|x| x.parse::<u8>().unwrap());
+fn main() -> Result<(), Box<dyn Error>> {
+    let input = string_from_arg()?;
+    let license = parse_license(&input, InputFormat::from_args(aoc::cli::args()))?;
+    println!("Answer A: {}", part_a(license.iter().cloned()));
+    println!("Answer B: {}", part_b(license.into_iter()));
+    Ok(())
+}
+
+#[test]
+fn test_parse_structured() {
+    let expected = Ok(vec![2u8, 3, 0, 3, 10, 11, 12, 1, 1, 0, 1, 99, 2, 1, 1, 2]);
+    let text = "2 3 0 3 10 11 12 1 1 0 1 99 2 1 1 2\n";
+    let json = r#"[{"license": [2, 3, 0, 3, 10, 11, 12, 1, 1, 0, 1, 99, 2, 1, 1, 2]}]"#;
+    let csv = "license\n2 3 0 3 10 11 12\n1 1 0 1 99 2 1 1 2\n";
 
-    println!("Answer A: {}", part_a(license.clone()));
-    println!("Answer B: {}", part_b(license));
+    assert_eq!(parse_license(text, InputFormat::Text), expected);
+    assert_eq!(parse_license(json, InputFormat::Json), expected);
+    assert_eq!(parse_license(csv, InputFormat::Csv), expected);
+    assert_eq!(
+        parse_license("2 3 0 x\n", InputFormat::Text),
+        Err(ParseError::AtLine {
+            line: 1,
+            error: Box::new(ParseError::NoMatch("x".to_owned()))
+        })
+    );
 }
 
 #[test]
@@ -0,0 +1,212 @@
+use crate::input::InputFormat;
+use crate::overflow::Overflow;
+use crate::trace;
+use lazy_static::lazy_static;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Command line arguments shared by all days.
+///
+/// Flags are picked out of the argument list and everything else is kept as
+/// positional arguments in the order they were given.
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub program: String,
+    pub verbosity: usize,
+    pub trace_file: Option<PathBuf>,
+    pub overflow: Overflow,
+    /// Delay between frames when animating the solver
+    pub animate: Option<Duration>,
+    /// Where to save a picture of the solution, for days that can draw one
+    pub image: Option<PathBuf>,
+    /// Directory to save one picture per step in, for days that can draw them
+    pub image_sequence: Option<PathBuf>,
+    /// Where to record every step of the solver, for days that support it
+    pub record: Option<PathBuf>,
+    /// How the input is represented, instead of guessing from its extension
+    pub input_format: Option<InputFormat>,
+    pub positional: Vec<String>,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        let program = args
+            .next()
+            .as_ref()
+            .and_then(|p| Path::new(p).file_name())
+            .and_then(|p| p.to_str())
+            .unwrap_or("aoc")
+            .to_owned();
+
+        let mut out = Args {
+            program,
+            ..Args::default()
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => {
+                    out.positional.extend(args);
+                    break;
+                }
+                "--verbose" => out.verbosity += 1,
+                "--trace-file" => {
+                    let path = args.next().ok_or("--trace-file requires a path")?;
+                    out.trace_file = Some(path.into());
+                }
+                "--overflow" => {
+                    let policy = args.next().ok_or("--overflow requires a policy")?;
+                    out.overflow = policy.parse()?;
+                }
+                "--animate" => {
+                    let delay = args
+                        .next()
+                        .and_then(|ms| ms.parse().ok())
+                        .ok_or("--animate requires a delay in milliseconds")?;
+                    out.animate = Some(Duration::from_millis(delay));
+                }
+                "--image" => {
+                    let path = args.next().ok_or("--image requires a path")?;
+                    out.image = Some(path.into());
+                }
+                "--image-sequence" => {
+                    let dir = args.next().ok_or("--image-sequence requires a directory")?;
+                    out.image_sequence = Some(dir.into());
+                }
+                "--record" => {
+                    let path = args.next().ok_or("--record requires a path")?;
+                    out.record = Some(path.into());
+                }
+                "--input-format" => {
+                    let format = args.next().ok_or("--input-format requires a format")?;
+                    out.input_format = Some(format.parse()?);
+                }
+                a if a.len() > 1 && a.starts_with('-') && a[1..].chars().all(|c| c == 'v') => {
+                    out.verbosity += a.len() - 1;
+                }
+                _ => out.positional.push(arg),
+            }
+        }
+        Ok(out)
+    }
+
+    /// Usage text with the flags that all days accept, followed by the given
+    /// positional arguments. Lines are wrapped to fit in 80 columns
+    pub fn usage(&self, positional: &str) -> String {
+        let mut usage = format!("Usage: {}", self.program);
+        let mut line_len = usage.len();
+        for word in FLAGS.iter().copied().chain(std::iter::once(positional)) {
+            if line_len + 1 + word.len() > 79 {
+                usage.push_str("\n   ");
+                line_len = 3;
+            }
+            usage.push(' ');
+            usage.push_str(word);
+            line_len += 1 + word.len();
+        }
+        usage
+    }
+}
+
+/// Flags that are parsed by `Args`, as shown in the usage text
+const FLAGS: [&str; 8] = [
+    "[-v|-vv]",
+    "[--trace-file <path>]",
+    "[--overflow <policy>]",
+    "[--animate <ms>]",
+    "[--image <path>]",
+    "[--image-sequence <dir>]",
+    "[--record <path>]",
+    "[--input-format <format>]",
+];
+
+lazy_static! {
+    static ref ARGS: Args = {
+        let args = match Args::parse(std::env::args()) {
+            Ok(args) => args,
+            Err(msg) => {
+                eprintln!("{}", msg);
+                std::process::exit(2);
+            }
+        };
+        if let Err(err) = trace::init_from_args(&args) {
+            eprintln!("Unable to set up tracing: {}", err);
+            std::process::exit(2);
+        }
+        args
+    };
+}
+
+/// Arguments of the current process. Tracing is set up on first access
+pub fn args() -> &'static Args {
+    &ARGS
+}
+
+#[test]
+fn test_parse() {
+    let args = |a: &[&str]| Args::parse(a.iter().map(|x| x.to_string()));
+
+    assert_eq!(
+        args(&[
+            "target/day9",
+            "-vv",
+            "411",
+            "--trace-file",
+            "out.json",
+            "71170",
+            "--overflow",
+            "wrapping",
+            "--animate",
+            "50",
+            "--image",
+            "day9.ppm",
+            "--image-sequence",
+            "frames",
+            "--record",
+            "day9.ndjson",
+            "--input-format",
+            "json"
+        ]),
+        Ok(Args {
+            program: "day9".to_owned(),
+            verbosity: 2,
+            trace_file: Some("out.json".into()),
+            overflow: Overflow::Wrapping,
+            animate: Some(Duration::from_millis(50)),
+            image: Some("day9.ppm".into()),
+            image_sequence: Some("frames".into()),
+            record: Some("day9.ndjson".into()),
+            input_format: Some(InputFormat::Json),
+            positional: vec!["411".to_owned(), "71170".to_owned()],
+        })
+    );
+    assert_eq!(args(&["day1", "-v", "--verbose"]).unwrap().verbosity, 2);
+    assert_eq!(
+        args(&["day1", "--", "-v", "-1"]).unwrap().positional,
+        vec!["-v".to_owned(), "-1".to_owned()]
+    );
+    assert!(args(&["day1", "--trace-file"]).is_err());
+    assert!(args(&["day1", "--overflow", "sometimes"]).is_err());
+    assert!(args(&["day1", "--animate", "fast"]).is_err());
+    assert!(args(&["day1", "--image"]).is_err());
+    assert!(args(&["day1", "--image-sequence"]).is_err());
+    assert!(args(&["day1", "--record"]).is_err());
+    assert!(args(&["day1", "--input-format", "yaml"]).is_err());
+}
+
+#[test]
+fn test_usage() {
+    let args = Args {
+        program: "day9".to_owned(),
+        ..Args::default()
+    };
+    let usage = args.usage("<path>|<arg-1> <arg-2>");
+    assert!(usage.starts_with("Usage: day9 [-v|-vv] [--trace-file <path>]"));
+    assert!(usage.ends_with(" <path>|<arg-1> <arg-2>"));
+    for line in usage.lines() {
+        assert!(line.len() < 80, "{:?} is too long", line);
+    }
+    for flag in FLAGS.iter() {
+        assert!(usage.contains(flag), "{} is missing", flag);
+    }
+}
@@ -0,0 +1,312 @@
+use crate::overflow::{convert, Overflow, OverflowError};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::ops::Range;
+
+const BITS: isize = 64;
+
+/// Growable row of bits that can be indexed by any integer. Unset bits extend
+/// infinitely in both directions, so only the words around the set bits are
+/// stored.
+///
+/// Equality and hashing only depend on which bits are set, not on how much
+/// room happens to be allocated around them
+#[derive(Clone, Default)]
+pub struct BitRow {
+    // Index of the lowest bit of the first word
+    offset: isize,
+    words: Vec<u64>,
+}
+
+impl BitRow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a row where the first value is put at `offset`
+    pub fn from_bools(offset: isize, values: impl IntoIterator<Item = bool>) -> Self {
+        let mut row = Self {
+            offset,
+            words: Vec::new(),
+        };
+        for (i, value) in values.into_iter().enumerate() {
+            if value {
+                row.set(offset + i as isize, true);
+            }
+        }
+        row
+    }
+
+    /// Position of `index` as word and bit, if it is within the stored words
+    fn locate(&self, index: isize) -> Option<(usize, u32)> {
+        let rel = index - self.offset;
+        if rel < 0 || rel >= BITS * self.words.len() as isize {
+            return None;
+        }
+        Some(((rel / BITS) as usize, (rel % BITS) as u32))
+    }
+
+    pub fn get(&self, index: isize) -> bool {
+        match self.locate(index) {
+            Some((word, bit)) => self.words[word] >> bit & 1 == 1,
+            None => false,
+        }
+    }
+
+    pub fn set(&mut self, index: isize, value: bool) {
+        if !value {
+            if let Some((word, bit)) = self.locate(index) {
+                self.words[word] &= !(1 << bit);
+            }
+            return;
+        }
+
+        if self.words.is_empty() {
+            self.offset = index.div_euclid(BITS) * BITS;
+            self.words.push(0);
+        } else if index < self.offset {
+            let missing = (self.offset - index + BITS - 1) / BITS;
+            self.words.splice(0..0, vec![0; missing as usize]);
+            self.offset -= missing * BITS;
+        }
+
+        let rel = index - self.offset;
+        let word = (rel / BITS) as usize;
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        self.words[word] |= 1 << (rel % BITS);
+    }
+
+    /// Index of the lowest set bit
+    pub fn first(&self) -> Option<isize> {
+        let (i, word) = self.words.iter().enumerate().find(|(_, &w)| w != 0)?;
+        Some(self.offset + i as isize * BITS + word.trailing_zeros() as isize)
+    }
+
+    /// Index of the highest set bit
+    pub fn last(&self) -> Option<isize> {
+        let (i, word) = self.words.iter().enumerate().rfind(|(_, &w)| w != 0)?;
+        Some(self.offset + i as isize * BITS + (BITS - 1) - word.leading_zeros() as isize)
+    }
+
+    /// Range from the first to the last set bit
+    pub fn range(&self) -> Option<Range<isize>> {
+        Some(self.first()?..self.last()? + 1)
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_clear(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    /// Move all bits `by` steps towards higher indices. This is O(1)
+    pub fn shift(&mut self, by: isize, overflow: Overflow) -> Result<(), OverflowError> {
+        self.offset = overflow.add(self.offset, by)?;
+        Ok(())
+    }
+
+    /// Copy of the row that is moved so the first set bit is at index 0.
+    /// Rows with the same pattern are equal no matter where they are
+    pub fn pattern(&self) -> Self {
+        let mut pattern = self.clone();
+        pattern.normalize();
+        pattern.offset = 0;
+        pattern
+    }
+
+    /// Release storage outside of the set bits, and align the first set bit
+    /// to the start of the first word
+    pub fn normalize(&mut self) {
+        match self.first() {
+            Some(first) => {
+                self.words = self.aligned_words().collect();
+                self.offset = first;
+            }
+            None => *self = Self::default(),
+        }
+    }
+
+    /// Stored bits as words starting at the first set bit. The last word is
+    /// the one containing the last set bit
+    fn aligned_words(&self) -> impl Iterator<Item = u64> + '_ {
+        let range = self.range().unwrap_or(0..0);
+        let num_words = (range.end - range.start + BITS - 1) / BITS;
+        let start = range.start - self.offset;
+        (0..num_words).map(move |i| {
+            let rel = start + i * BITS;
+            let (word, bit) = ((rel / BITS) as usize, (rel % BITS) as u32);
+            let low = self.words[word] >> bit;
+            let high = match self.words.get(word + 1) {
+                Some(next) if bit > 0 => next << (BITS as u32 - bit),
+                _ => 0,
+            };
+            low | high
+        })
+    }
+
+    /// Indices of all set bits in increasing order
+    pub fn ones(&self) -> impl Iterator<Item = isize> + '_ {
+        self.words.iter().enumerate().flat_map(move |(i, &word)| {
+            let base = self.offset + i as isize * BITS;
+            let mut rest = word;
+            std::iter::from_fn(move || {
+                if rest == 0 {
+                    return None;
+                }
+                let bit = rest.trailing_zeros() as isize;
+                rest &= rest - 1;
+                Some(base + bit)
+            })
+        })
+    }
+
+    /// Sum of the indices of all set bits, which overflows according to the
+    /// given policy
+    pub fn index_sum(&self, overflow: Overflow) -> Result<isize, OverflowError> {
+        let mut sum = 0;
+        for (i, &word) in self.words.iter().enumerate().filter(|(_, &word)| word != 0) {
+            // Every set bit contributes the base index of its word, plus its
+            // position within the word
+            let base = overflow.add(self.offset, overflow.mul(convert(i)?, BITS)?)?;
+            let mut positions = 0;
+            let mut rest = word;
+            while rest != 0 {
+                positions += rest.trailing_zeros() as isize;
+                rest &= rest - 1;
+            }
+            let bases = overflow.mul(base, word.count_ones() as isize)?;
+            sum = overflow.add(sum, overflow.add(bases, positions)?)?;
+        }
+        Ok(sum)
+    }
+}
+
+impl PartialEq for BitRow {
+    fn eq(&self, other: &Self) -> bool {
+        self.first() == other.first() && self.aligned_words().eq(other.aligned_words())
+    }
+}
+
+impl Eq for BitRow {}
+
+impl Hash for BitRow {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.first().hash(state);
+        for word in self.aligned_words() {
+            word.hash(state);
+        }
+    }
+}
+
+/// Collects indices of set bits
+impl FromIterator<isize> for BitRow {
+    fn from_iter<I: IntoIterator<Item = isize>>(iter: I) -> Self {
+        let mut row = Self::new();
+        for index in iter {
+            row.set(index, true);
+        }
+        row
+    }
+}
+
+/// Renders the bits from the first to the last set one as `#` and `.`
+impl fmt::Display for BitRow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for i in self.range().unwrap_or(0..0) {
+            write!(f, "{}", if self.get(i) { '#' } else { '.' })?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for BitRow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BitRow<{}, {}>", self.first().unwrap_or(0), self)
+    }
+}
+
+#[test]
+fn test_get_set() {
+    let mut row = BitRow::new();
+    assert!(row.is_clear());
+    assert_eq!(row.first(), None);
+
+    row.set(3, true);
+    row.set(-70, true);
+    row.set(130, true);
+    assert!(row.get(3) && row.get(-70) && row.get(130));
+    assert!(!row.get(4) && !row.get(-1000) && !row.get(1000));
+    assert_eq!(row.range(), Some(-70..131));
+    assert_eq!(row.count_ones(), 3);
+    assert_eq!(row.ones().collect::<Vec<_>>(), vec![-70, 3, 130]);
+    assert_eq!(row.index_sum(Overflow::Checked), Ok(63));
+
+    row.set(-70, false);
+    row.set(500, false);
+    assert_eq!(row.first(), Some(3));
+    assert_eq!(row.index_sum(Overflow::Checked), Ok(133));
+}
+
+#[test]
+fn test_shift_normalize() {
+    let mut row = BitRow::from_bools(-2, "#..##.#".chars().map(|c| c == '#'));
+    assert_eq!(row.to_string(), "#..##.#");
+    assert_eq!(format!("{:?}", row), "BitRow<-2, #..##.#>");
+    assert_eq!(row.index_sum(Overflow::Checked), Ok(-2 + 1 + 2 + 4));
+
+    let copy = row.clone();
+    row.shift(1000, Overflow::Checked).unwrap();
+    assert_eq!(row.first(), Some(998));
+    assert_eq!(
+        row.index_sum(Overflow::Checked),
+        Ok(998 + 1001 + 1002 + 1004)
+    );
+    assert_ne!(row, copy);
+    assert_eq!(row.pattern(), copy.pattern());
+
+    assert!(row.shift(isize::MAX, Overflow::Checked).is_err());
+    assert_eq!(row.first(), Some(998));
+
+    row.normalize();
+    assert_eq!(row.words.len(), 1);
+    assert_eq!(row.offset, 998);
+    assert_eq!(row.to_string(), "#..##.#");
+}
+
+#[test]
+fn test_index_sum_overflow() {
+    let row = BitRow::from_bools(isize::MAX - 100, vec![true, true]);
+    assert!(row.index_sum(Overflow::Checked).is_err());
+    assert_eq!(
+        row.index_sum(Overflow::Wrapping),
+        Ok((isize::MAX - 100).wrapping_mul(2).wrapping_add(1))
+    );
+    assert_eq!(row.index_sum(Overflow::Saturating), Ok(isize::MAX));
+}
+
+#[test]
+fn test_eq_hash() {
+    use std::collections::HashSet;
+
+    // Same bits stored with different amounts of padding and alignment
+    let a: BitRow = vec![5, 64, 200].into_iter().collect();
+    let mut b = BitRow::from_bools(-300, std::iter::empty());
+    for &i in &[200, 5, 64, -299] {
+        b.set(i, true);
+    }
+    assert_ne!(a, b);
+    b.set(-299, false);
+    assert_eq!(a, b);
+
+    let mut set = HashSet::new();
+    set.insert(a);
+    assert!(set.contains(&b));
+    b.normalize();
+    assert!(set.contains(&b));
+    assert!(!set.contains(&BitRow::new()));
+}
@@ -0,0 +1,146 @@
+/// Union-find structure that keeps track of which elements `0..len` belong to
+/// the same component
+#[derive(Clone, Debug, Default)]
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    size: Vec<usize>,
+    num_components: usize,
+}
+
+impl DisjointSet {
+    /// Create `len` elements that are all in their own component
+    pub fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            rank: vec![0; len],
+            size: vec![1; len],
+            num_components: len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Add a new element in its own component and return it
+    pub fn add(&mut self) -> usize {
+        let element = self.parent.len();
+        self.parent.push(element);
+        self.rank.push(0);
+        self.size.push(1);
+        self.num_components += 1;
+        element
+    }
+
+    /// Representative of the component that the element belongs to. All
+    /// elements on the way are pointed directly to it to speed up later
+    /// lookups
+    pub fn find(&mut self, element: usize) -> usize {
+        let mut root = element;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        let mut current = element;
+        while self.parent[current] != root {
+            let next = self.parent[current];
+            self.parent[current] = root;
+            current = next;
+        }
+        root
+    }
+
+    /// Merge the components of the two elements. Returns false if they were
+    /// already in the same component
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+
+        // Attach the shallower tree below the deeper one to keep them flat
+        let (root, child) = if self.rank[a] < self.rank[b] {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        self.parent[child] = root;
+        self.size[root] += self.size[child];
+        if self.rank[root] == self.rank[child] {
+            self.rank[root] += 1;
+        }
+        self.num_components -= 1;
+        true
+    }
+
+    pub fn same(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    pub fn num_components(&self) -> usize {
+        self.num_components
+    }
+
+    /// Number of elements in the component that the element belongs to
+    pub fn component_size(&mut self, element: usize) -> usize {
+        let root = self.find(element);
+        self.size[root]
+    }
+
+    /// Elements of every component in increasing order. The components are
+    /// ordered by their smallest element
+    pub fn components(&mut self) -> Vec<Vec<usize>> {
+        let mut index_of_root = vec![None; self.len()];
+        let mut components: Vec<Vec<usize>> = Vec::with_capacity(self.num_components);
+        for element in 0..self.len() {
+            let root = self.find(element);
+            let index = *index_of_root[root].get_or_insert_with(|| {
+                components.push(Vec::with_capacity(self.size[root]));
+                components.len() - 1
+            });
+            components[index].push(element);
+        }
+        components
+    }
+}
+
+#[test]
+fn test_union_find() {
+    let mut set = DisjointSet::new(6);
+    assert_eq!(set.num_components(), 6);
+
+    assert!(set.union(0, 3));
+    assert!(set.union(4, 3));
+    assert!(set.union(1, 5));
+    assert!(!set.union(0, 4));
+
+    assert!(set.same(0, 4));
+    assert!(!set.same(0, 1));
+    assert_eq!(set.num_components(), 3);
+    assert_eq!(set.component_size(3), 3);
+    assert_eq!(set.component_size(2), 1);
+    assert_eq!(set.components(), vec![vec![0, 3, 4], vec![1, 5], vec![2]]);
+
+    let extra = set.add();
+    assert_eq!(extra, 6);
+    set.union(extra, 2);
+    assert_eq!(set.num_components(), 3);
+    assert_eq!(set.components()[2], vec![2, 6]);
+}
+
+#[test]
+fn test_long_chain() {
+    // Deep chains must not overflow the stack or degrade lookups
+    let mut set = DisjointSet::new(100_000);
+    for i in 1..set.len() {
+        set.union(i - 1, i);
+    }
+    assert_eq!(set.num_components(), 1);
+    assert_eq!(set.component_size(12_345), 100_000);
+    assert!(set.same(0, 99_999));
+}
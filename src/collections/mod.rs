@@ -0,0 +1,7 @@
+pub mod bitrow;
+pub mod disjoint_set;
+pub mod ouroboros;
+
+pub use self::bitrow::BitRow;
+pub use self::disjoint_set::DisjointSet;
+pub use self::ouroboros::Ouroboros;
@@ -0,0 +1,433 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+struct Node<T> {
+    prev: NonNull<Node<T>>,
+    next: NonNull<Node<T>>,
+    value: T,
+}
+
+impl<T> Node<T> {
+    /// Allocate a node that is linked to itself
+    fn new_raw(value: T) -> NonNull<Self> {
+        let boxed = Box::new(Self {
+            prev: NonNull::dangling(),
+            next: NonNull::dangling(),
+            value,
+        });
+        let ptr = NonNull::from(Box::leak(boxed));
+        unsafe {
+            (*ptr.as_ptr()).prev = ptr;
+            (*ptr.as_ptr()).next = ptr;
+        }
+        ptr
+    }
+}
+
+/// Put `node` in between `prev` and `next`, which must be adjacent. Fields
+/// are only accessed through raw pointers since `prev` and `next` may be the
+/// same node
+unsafe fn link<T>(prev: NonNull<Node<T>>, node: NonNull<Node<T>>, next: NonNull<Node<T>>) {
+    (*node.as_ptr()).prev = prev;
+    (*node.as_ptr()).next = next;
+    (*prev.as_ptr()).next = node;
+    (*next.as_ptr()).prev = node;
+}
+
+/// Connect the neighbours of `node` to each other. The node itself is left
+/// untouched
+unsafe fn unlink<T>(node: NonNull<Node<T>>) {
+    let prev = (*node.as_ptr()).prev;
+    let next = (*node.as_ptr()).next;
+    (*prev.as_ptr()).next = next;
+    (*next.as_ptr()).prev = prev;
+}
+
+/// Circular doubly linked list with a cursor. All operations are relative to
+/// the cursor, which makes inserting and removing values next to it O(1).
+///
+/// Like `LinkedList` it can only be sent to or shared with other threads when
+/// its values can:
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<aoc::collections::Ouroboros<std::rc::Rc<u8>>>();
+/// ```
+pub struct Ouroboros<T> {
+    // Every node in the ring is owned by the list and was allocated using
+    // `Node::new_raw`. `len` is the number of nodes in the ring
+    current: Option<NonNull<Node<T>>>,
+    len: usize,
+    marker: PhantomData<Box<Node<T>>>,
+}
+
+// The list owns its values just like `LinkedList`, so it is safe to send or
+// share it whenever the values are
+unsafe impl<T: Send> Send for Ouroboros<T> {}
+unsafe impl<T: Sync> Sync for Ouroboros<T> {}
+
+impl<T> Ouroboros<T> {
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            len: 0,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Insert a value after the cursor without moving it. If the list is
+    /// empty the cursor is placed on the new value
+    pub fn insert_after(&mut self, value: T) {
+        let node = Node::new_raw(value);
+        match self.current {
+            Some(current) => unsafe { link(current, node, (*current.as_ptr()).next) },
+            None => self.current = Some(node),
+        }
+        self.len += 1;
+    }
+
+    /// Insert a value before the cursor without moving it. If the list is
+    /// empty the cursor is placed on the new value
+    pub fn insert_before(&mut self, value: T) {
+        let node = Node::new_raw(value);
+        match self.current {
+            Some(current) => unsafe { link((*current.as_ptr()).prev, node, current) },
+            None => self.current = Some(node),
+        }
+        self.len += 1;
+    }
+
+    /// Value under the cursor
+    pub fn peek(&self) -> Option<&T> {
+        self.current.map(|c| unsafe { &(*c.as_ptr()).value })
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.current.map(|c| unsafe { &mut (*c.as_ptr()).value })
+    }
+
+    /// Remove the value under the cursor. The cursor moves to the next value
+    pub fn take(&mut self) -> Option<T> {
+        let current = self.current?;
+        unsafe {
+            self.current = if self.len > 1 {
+                unlink(current);
+                Some((*current.as_ptr()).next)
+            } else {
+                None
+            };
+            self.len -= 1;
+
+            // The node is no longer reachable from the list, so ownership can
+            // be taken back
+            Some(Box::from_raw(current.as_ptr()).value)
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        if let Some(c) = self.current {
+            self.current = Some(unsafe { (*c.as_ptr()).prev });
+        }
+    }
+
+    pub fn move_next(&mut self) {
+        if let Some(c) = self.current {
+            self.current = Some(unsafe { (*c.as_ptr()).next });
+        }
+    }
+
+    /// Move the cursor forward, or backward for negative steps. Whole laps
+    /// are skipped and the shortest direction is used
+    pub fn move_by(&mut self, steps: isize) {
+        if self.len == 0 {
+            return;
+        }
+
+        let forward = steps.rem_euclid(self.len as isize) as usize;
+        if forward <= self.len / 2 {
+            for _ in 0..forward {
+                self.move_next();
+            }
+        } else {
+            for _ in forward..self.len {
+                self.move_prev();
+            }
+        }
+    }
+
+    /// Iterate over all values once, starting at the cursor
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            node: self.current,
+            remaining: self.len,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for Ouroboros<T> {
+    fn drop(&mut self) {
+        while self.take().is_some() {}
+    }
+}
+
+impl<T> Default for Ouroboros<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> Clone for Ouroboros<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+/// Values are added before the cursor, which means that they end up last
+/// when iterating from the cursor
+impl<T> Extend<T> for Ouroboros<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert_before(value);
+        }
+    }
+}
+
+/// The cursor is placed on the first value
+impl<T> FromIterator<T> for Ouroboros<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut ouroboros = Self::new();
+        ouroboros.extend(iter);
+        ouroboros
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Ouroboros<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Ouroboros")?;
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+pub struct Iter<'a, T> {
+    node: Option<NonNull<Node<T>>>,
+    remaining: usize,
+    marker: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let node = self.node?.as_ptr();
+        unsafe {
+            self.node = Some((*node).next);
+            Some(&(*node).value)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+unsafe impl<'a, T: Sync> Send for Iter<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for Iter<'a, T> {}
+
+impl<'a, T> IntoIterator for &'a Ouroboros<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[test]
+fn test_ouroboros() {
+    let mut circle = Ouroboros::new();
+    assert_eq!(circle.len(), 0);
+
+    circle.insert_after(1);
+    circle.insert_after(3);
+    circle.insert_after(2);
+
+    assert_eq!(circle.len(), 3);
+    assert_eq!(circle.peek(), Some(&1));
+
+    circle.move_next();
+    assert_eq!(circle.peek(), Some(&2));
+    circle.move_next();
+    assert_eq!(circle.peek(), Some(&3));
+    circle.move_next();
+    assert_eq!(circle.peek(), Some(&1));
+
+    circle.move_prev();
+    assert_eq!(circle.peek(), Some(&3));
+
+    assert_eq!(circle.take(), Some(3));
+    assert_eq!(circle.take(), Some(1));
+    assert_eq!(circle.take(), Some(2));
+    assert_eq!(circle.len(), 0);
+
+    assert_eq!(circle.take(), None);
+    assert_eq!(circle.peek(), None);
+}
+
+#[test]
+fn test_insert_before() {
+    let mut circle = Ouroboros::new();
+    circle.insert_before(1);
+    circle.insert_before(2);
+    circle.insert_after(3);
+    circle.insert_before(4);
+    assert_eq!(circle.peek(), Some(&1));
+    assert_eq!(circle.iter().collect::<Vec<_>>(), vec![&1, &3, &2, &4]);
+
+    *circle.peek_mut().unwrap() = 5;
+    circle.move_prev();
+    assert_eq!(circle.iter().collect::<Vec<_>>(), vec![&4, &5, &3, &2]);
+}
+
+#[test]
+fn test_move_by() {
+    let mut circle: Ouroboros<_> = (0..5).collect();
+    circle.move_by(2);
+    assert_eq!(circle.peek(), Some(&2));
+    circle.move_by(4);
+    assert_eq!(circle.peek(), Some(&1));
+    circle.move_by(-3);
+    assert_eq!(circle.peek(), Some(&3));
+    circle.move_by(-12);
+    assert_eq!(circle.peek(), Some(&1));
+    circle.move_by(10);
+    assert_eq!(circle.peek(), Some(&1));
+
+    let mut empty = Ouroboros::<u8>::new();
+    empty.move_by(3);
+    assert_eq!(empty.peek(), None);
+}
+
+#[test]
+fn test_collect_clone_debug() {
+    let mut circle: Ouroboros<_> = vec!['a', 'b'].into_iter().collect();
+    circle.extend("cd".chars());
+    circle.move_next();
+
+    let copy = circle.clone();
+    assert_eq!(circle.take(), Some('b'));
+    assert_eq!(format!("{:?}", circle), "Ouroboros['c', 'd', 'a']");
+    assert_eq!(format!("{:?}", copy), "Ouroboros['b', 'c', 'd', 'a']");
+    assert_eq!(copy.iter().len(), 4);
+}
+
+/// Small xorshift generator so the random tests are reproducible
+#[cfg(test)]
+struct Rng(u64);
+
+#[cfg(test)]
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+#[test]
+fn test_random_operations() {
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    // The model keeps the value under the cursor at the front, followed by the
+    // rest in iteration order. Every value holds a reference to `alive`, which
+    // makes it possible to check that nothing is leaked
+    let alive = Rc::new(());
+    let (runs, steps) = if cfg!(miri) { (4, 100) } else { (50, 1000) };
+
+    for seed in 1..=runs {
+        let mut rng = Rng(seed);
+        let mut circle = Ouroboros::new();
+        let mut model = VecDeque::new();
+
+        for i in 0..steps {
+            let value = (i, Rc::clone(&alive));
+            match rng.below(8) {
+                0 | 1 => {
+                    circle.insert_after(value.clone());
+                    model.insert(model.len().min(1), value);
+                }
+                2 => {
+                    circle.insert_before(value.clone());
+                    model.push_back(value);
+                }
+                3 => assert_eq!(circle.take(), model.pop_front()),
+                4 => {
+                    circle.move_next();
+                    if !model.is_empty() {
+                        model.rotate_left(1);
+                    }
+                }
+                5 => {
+                    circle.move_prev();
+                    if !model.is_empty() {
+                        model.rotate_right(1);
+                    }
+                }
+                6 => {
+                    let steps = rng.below(41) as isize - 20;
+                    circle.move_by(steps);
+                    if !model.is_empty() {
+                        model.rotate_left(steps.rem_euclid(model.len() as isize) as usize);
+                    }
+                }
+                _ => {
+                    if let Some(value) = circle.peek_mut() {
+                        value.0 += 1000;
+                    }
+                    if let Some(value) = model.front_mut() {
+                        value.0 += 1000;
+                    }
+                }
+            }
+
+            assert_eq!(circle.len(), model.len());
+            assert_eq!(circle.peek(), model.front());
+            assert!(circle.iter().eq(model.iter()));
+        }
+
+        let copy = circle.clone();
+        assert!(copy.iter().eq(model.iter()));
+    }
+
+    assert_eq!(Rc::strong_count(&alive), 1);
+}
+
+#[test]
+fn test_auto_traits() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Ouroboros<u32>>();
+    assert_send_sync::<Iter<'static, u32>>();
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Sub;
+
+/// A repeating part of a sequence of states. The state at generation
+/// `start + length` is equivalent to the one at `start`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    /// Generation before the end of the first cycle that is equivalent to
+    /// generation `n`, along with how many whole cycles lie between them
+    pub fn equivalent(&self, n: usize) -> (usize, usize) {
+        if n < self.start {
+            return (n, 0);
+        }
+        let offset = n - self.start;
+        (self.start + offset % self.length, offset / self.length)
+    }
+
+    /// How much a property of the states changes over one cycle. The states
+    /// must at least reach the end of the first cycle
+    pub fn delta<S, T: Sub<Output = T>>(&self, states: &[S], mut f: impl FnMut(&S) -> T) -> T {
+        f(&states[self.start + self.length]) - f(&states[self.start])
+    }
+
+    /// State at generation `n`. The equivalent state is looked up and passed
+    /// to `shift` along with the number of cycles it must be advanced. Since
+    /// advancing far ahead may overflow, `shift` is allowed to fail
+    pub fn extrapolate<S: Clone, E>(
+        &self,
+        states: &[S],
+        n: usize,
+        shift: impl FnOnce(&S, usize) -> Result<S, E>,
+    ) -> Result<S, E> {
+        let (gen, num_cycles) = self.equivalent(n);
+        if num_cycles == 0 {
+            Ok(states[gen].clone())
+        } else {
+            shift(&states[gen], num_cycles)
+        }
+    }
+}
+
+/// Records a sequence of states and detects when a state repeats. States are
+/// compared by the key they are normalized to, which makes it possible to
+/// ignore things like position when only the shape matters
+pub struct CycleDetector<S, K, F> {
+    states: Vec<S>,
+    seen: HashMap<K, usize>,
+    key: F,
+}
+
+impl<S, K, F> CycleDetector<S, K, F>
+where
+    K: Eq + Hash,
+    F: FnMut(&S) -> K,
+{
+    pub fn new(key: F) -> Self {
+        Self {
+            states: Vec::new(),
+            seen: HashMap::new(),
+            key,
+        }
+    }
+
+    /// Add the next state. Returns the cycle if this state has been seen
+    /// before
+    pub fn push(&mut self, state: S) -> Option<Cycle> {
+        let gen = self.states.len();
+        let key = (self.key)(&state);
+        self.states.push(state);
+
+        match self.seen.get(&key) {
+            Some(&start) => Some(Cycle {
+                start,
+                length: gen - start,
+            }),
+            None => {
+                self.seen.insert(key, gen);
+                None
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    pub fn last(&self) -> Option<&S> {
+        self.states.last()
+    }
+
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    pub fn into_states(self) -> Vec<S> {
+        self.states
+    }
+}
+
+/// Step from the initial state until a cycle is found, or `max_gens`
+/// generations have been generated. Returns all states that were seen
+pub fn find_cycle<S, K>(
+    initial: S,
+    mut step: impl FnMut(&S) -> S,
+    key: impl FnMut(&S) -> K,
+    max_gens: usize,
+) -> (Vec<S>, Option<Cycle>)
+where
+    K: Eq + Hash,
+{
+    let mut detector = CycleDetector::new(key);
+    detector.push(initial);
+    for _ in 0..max_gens {
+        let next = step(detector.last().unwrap());
+        if let Some(cycle) = detector.push(next) {
+            return (detector.into_states(), Some(cycle));
+        }
+    }
+    (detector.into_states(), None)
+}
+
+#[test]
+fn test_find_cycle() {
+    // Repeats every third step after a short lead-in, while drifting upwards
+    // by 10 per cycle
+    let step = |&(i, drift): &(i32, i32)| {
+        if i < 2 {
+            (i + 1, drift)
+        } else if i == 4 {
+            (2, drift + 10)
+        } else {
+            (i + 1, drift)
+        }
+    };
+    let (states, cycle) = find_cycle((0, 0), step, |&(i, _)| i, 100);
+    let cycle = cycle.unwrap();
+    assert_eq!(
+        cycle,
+        Cycle {
+            start: 2,
+            length: 3
+        }
+    );
+    assert_eq!(states.len(), 6);
+    assert_eq!(cycle.delta(&states, |&(_, drift)| drift), 10);
+
+    assert_eq!(cycle.equivalent(1), (1, 0));
+    assert_eq!(cycle.equivalent(4), (4, 0));
+    assert_eq!(cycle.equivalent(5), (2, 1));
+    assert_eq!(cycle.equivalent(1_000_000_000), (4, 333_333_332));
+
+    // Compare extrapolation with brute force
+    let extrapolate = |n| {
+        cycle.extrapolate(&states, n, |&(i, drift), num_cycles| {
+            Ok::<_, ()>((i, drift + num_cycles as i32 * 10))
+        })
+    };
+    let mut state = (0, 0);
+    for n in 0..50 {
+        assert_eq!(extrapolate(n), Ok(state));
+        state = step(&state);
+    }
+}
+
+#[test]
+fn test_no_cycle() {
+    let (states, cycle) = find_cycle(0u64, |&i| i + 1, |&i| i, 10);
+    assert_eq!(cycle, None);
+    assert_eq!(states.len(), 11);
+}
@@ -0,0 +1,347 @@
+use crate::parse::{capture_from_str, FromCapture, ParseError};
+use regex::Match;
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// Signed integer type that can be used as a coordinate
+pub trait Coord:
+    Copy
+    + Default
+    + Ord
+    + fmt::Debug
+    + fmt::Display
+    + FromStr
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Neg<Output = Self>
+{
+    const ONE: Self;
+
+    fn abs(self) -> Self;
+}
+
+macro_rules! impl_coord {
+    ($($t:ty),*) => {
+        $(
+            impl Coord for $t {
+                const ONE: Self = 1;
+
+                fn abs(self) -> Self {
+                    <$t>::abs(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_coord!(i8, i16, i32, i64, i128, isize);
+
+macro_rules! point {
+    ($name:ident, $num_dims:expr, $($field:ident),+) => {
+        #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+        pub struct $name<T = i64> {
+            $(pub $field: T),+
+        }
+
+        impl<T: Coord> $name<T> {
+            pub fn new($($field: T),+) -> Self {
+                Self { $($field),+ }
+            }
+
+            pub fn origin() -> Self {
+                Self::default()
+            }
+
+            /// Manhattan (taxicab) distance between the two points
+            pub fn manhattan(self, other: Self) -> T {
+                (self - other).manhattan_len()
+            }
+
+            /// Manhattan distance to the origin
+            pub fn manhattan_len(self) -> T {
+                T::default() $(+ self.$field.abs())+
+            }
+
+            /// Component-wise minimum
+            pub fn min(self, other: Self) -> Self {
+                Self { $($field: self.$field.min(other.$field)),+ }
+            }
+
+            /// Component-wise maximum
+            pub fn max(self, other: Self) -> Self {
+                Self { $($field: self.$field.max(other.$field)),+ }
+            }
+
+            /// Smallest and largest corner of the box containing all points,
+            /// inclusive
+            pub fn bounds(points: impl IntoIterator<Item = Self>) -> Option<(Self, Self)> {
+                let mut points = points.into_iter();
+                let first = points.next()?;
+                Some(points.fold((first, first), |(min, max), p| (min.min(p), max.max(p))))
+            }
+        }
+
+        impl<T: Coord> Add for $name<T> {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                Self { $($field: self.$field + other.$field),+ }
+            }
+        }
+
+        impl<T: Coord> Sub for $name<T> {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self {
+                Self { $($field: self.$field - other.$field),+ }
+            }
+        }
+
+        impl<T: Coord> Neg for $name<T> {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self { $($field: -self.$field),+ }
+            }
+        }
+
+        impl<T: Coord> Mul<T> for $name<T> {
+            type Output = Self;
+
+            fn mul(self, factor: T) -> Self {
+                Self { $($field: self.$field * factor),+ }
+            }
+        }
+
+        impl<T: Coord> AddAssign for $name<T> {
+            fn add_assign(&mut self, other: Self) {
+                *self = *self + other;
+            }
+        }
+
+        impl<T: Coord> SubAssign for $name<T> {
+            fn sub_assign(&mut self, other: Self) {
+                *self = *self - other;
+            }
+        }
+
+        impl<T: Coord> fmt::Display for $name<T> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let values = [$(self.$field.to_string()),+];
+                write!(f, "{}", values.join(","))
+            }
+        }
+
+        /// Parses comma separated coordinates like `3,4` or `<3, -4>`
+        impl<T: Coord> FromStr for $name<T> {
+            type Err = ParseError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let inner = s
+                    .trim()
+                    .trim_start_matches(['<', '(', '['])
+                    .trim_end_matches(['>', ')', ']']);
+                let parts: Vec<&str> = inner.split(',').map(str::trim).collect();
+                if parts.len() != $num_dims {
+                    return Err(ParseError::NoMatch(s.to_owned()));
+                }
+
+                let mut parts = parts.into_iter().enumerate();
+                Ok(Self {
+                    $($field: {
+                        let (i, value) = parts.next().unwrap();
+                        value.parse().map_err(|_| ParseError::InvalidValue {
+                            group: i + 1,
+                            value: value.to_owned(),
+                        })?
+                    }),+
+                })
+            }
+        }
+
+        impl<T: Coord> FromCapture for $name<T> {
+            fn from_capture(group: usize, capture: Option<Match>) -> Result<Self, ParseError> {
+                capture_from_str(group, capture)
+            }
+        }
+    };
+}
+
+point!(Point2, 2, x, y);
+point!(Point3, 3, x, y, z);
+point!(Point4, 4, x, y, z, w);
+
+/// Axis aligned rectangle covering `min.x..max.x` and `min.y..max.y`. The
+/// maximum corner is exclusive, which makes empty rectangles representable
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Rect<T = i64> {
+    pub min: Point2<T>,
+    pub max: Point2<T>,
+}
+
+impl<T: Coord> Rect<T> {
+    pub fn new(min: Point2<T>, max: Point2<T>) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_size(pos: Point2<T>, width: T, height: T) -> Self {
+        Self {
+            min: pos,
+            max: pos + Point2::new(width, height),
+        }
+    }
+
+    /// Smallest rectangle that contains all points
+    pub fn bounding(points: impl IntoIterator<Item = Point2<T>>) -> Option<Self> {
+        let (min, max) = Point2::bounds(points)?;
+        Some(Self::new(min, max + Point2::new(T::ONE, T::ONE)))
+    }
+
+    pub fn width(&self) -> T {
+        (self.max.x - self.min.x).max(T::default())
+    }
+
+    pub fn height(&self) -> T {
+        (self.max.y - self.min.y).max(T::default())
+    }
+
+    pub fn area(&self) -> T {
+        self.width() * self.height()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width() == T::default() || self.height() == T::default()
+    }
+
+    pub fn contains(&self, p: Point2<T>) -> bool {
+        self.min.x <= p.x && p.x < self.max.x && self.min.y <= p.y && p.y < self.max.y
+    }
+
+    /// True if the other rectangle is completely covered by this one
+    pub fn contains_rect(&self, other: &Self) -> bool {
+        other.is_empty()
+            || (self.min.x <= other.min.x
+                && other.max.x <= self.max.x
+                && self.min.y <= other.min.y
+                && other.max.y <= self.max.y)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let rect = Self::new(self.min.max(other.min), self.max.min(other.max));
+        if rect.is_empty() {
+            None
+        } else {
+            Some(rect)
+        }
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// Smallest rectangle that covers both rectangles
+    pub fn hull(&self, other: &Self) -> Self {
+        if self.is_empty() {
+            *other
+        } else if other.is_empty() {
+            *self
+        } else {
+            Self::new(self.min.min(other.min), self.max.max(other.max))
+        }
+    }
+
+    /// All points inside the rectangle, row by row
+    pub fn points(&self) -> impl Iterator<Item = Point2<T>> {
+        let rect = *self;
+        let mut next = if rect.is_empty() {
+            None
+        } else {
+            Some(rect.min)
+        };
+        std::iter::from_fn(move || {
+            let current = next?;
+            let mut following = current + Point2::new(T::ONE, T::default());
+            if following.x >= rect.max.x {
+                following = Point2::new(rect.min.x, current.y + T::ONE);
+            }
+            next = if following.y < rect.max.y {
+                Some(following)
+            } else {
+                None
+            };
+            Some(current)
+        })
+    }
+}
+
+#[test]
+fn test_point_ops() {
+    let a = Point2::new(1, -2);
+    let b = Point2::new(-3, 4);
+    assert_eq!(a + b, Point2::new(-2, 2));
+    assert_eq!(a - b, Point2::new(4, -6));
+    assert_eq!(-a, Point2::new(-1, 2));
+    assert_eq!(a * 3, Point2::new(3, -6));
+    assert_eq!(a.manhattan(b), 10);
+    assert_eq!(Point4::new(1, -1, 1, -1).manhattan_len(), 4);
+
+    let mut c = Point3::new(1i32, 2, 3);
+    c += Point3::new(1, 1, 1);
+    c -= Point3::new(0, 0, 5);
+    assert_eq!(c, Point3::new(2, 3, -1));
+}
+
+#[test]
+fn test_point_parse() {
+    assert_eq!("3,4".parse(), Ok(Point2::new(3, 4)));
+    assert_eq!("< 3, -4>".parse(), Ok(Point2::new(3, -4)));
+    assert_eq!("1,2,3,-4".parse(), Ok(Point4::new(1, 2, 3, -4)));
+    assert_eq!(
+        "1,2".parse::<Point3>(),
+        Err(ParseError::NoMatch("1,2".to_owned()))
+    );
+    assert_eq!(
+        "1,x".parse::<Point2>(),
+        Err(ParseError::InvalidValue {
+            group: 2,
+            value: "x".to_owned()
+        })
+    );
+    assert_eq!(Point3::new(1, -2, 3).to_string(), "1,-2,3");
+}
+
+#[test]
+fn test_rect() {
+    let a = Rect::from_size(Point2::new(1, 3), 4, 4);
+    let b = Rect::from_size(Point2::new(3, 1), 4, 4);
+    let c = Rect::from_size(Point2::new(5, 5), 2, 2);
+
+    assert_eq!(a.area(), 16);
+    assert_eq!(
+        a.intersection(&b),
+        Some(Rect::new(Point2::new(3, 3), Point2::new(5, 5)))
+    );
+    assert!(!a.intersects(&c));
+    assert!(a.contains(Point2::new(4, 6)));
+    assert!(!a.contains(Point2::new(5, 6)));
+    assert!(a.hull(&b).contains_rect(&a));
+    assert!(a.hull(&b).contains_rect(&b));
+    assert!(!a.hull(&c).contains_rect(&b));
+    assert_eq!(a.points().count(), 16);
+    assert_eq!(
+        c.points().collect::<Vec<_>>(),
+        vec![
+            Point2::new(5, 5),
+            Point2::new(6, 5),
+            Point2::new(5, 6),
+            Point2::new(6, 6)
+        ]
+    );
+    assert_eq!(
+        Rect::bounding(vec![Point2::new(1, 5), Point2::new(-2, 3)]),
+        Some(Rect::new(Point2::new(-2, 3), Point2::new(2, 6)))
+    );
+    assert_eq!(Rect::<i64>::default().points().count(), 0);
+}
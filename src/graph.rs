@@ -0,0 +1,441 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+/// Directed graph with weighted edges. Nodes and edges are kept in insertion
+/// order so that all algorithms are deterministic
+#[derive(Clone, Debug)]
+pub struct Graph<N> {
+    nodes: Vec<N>,
+    indices: HashMap<N, usize>,
+    successors: Vec<Vec<(usize, u64)>>,
+    predecessors: Vec<Vec<usize>>,
+}
+
+/// Returned by a topological sort when the graph contains a cycle. Holds the
+/// nodes that could not be sorted, which are the nodes on cycles and the nodes
+/// that depend on them
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CycleError<N> {
+    pub unsorted: Vec<N>,
+}
+
+impl<N: Clone + Eq + Hash> Graph<N> {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            indices: HashMap::new(),
+            successors: Vec::new(),
+            predecessors: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn contains(&self, node: &N) -> bool {
+        self.indices.contains_key(node)
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &N> {
+        self.nodes.iter()
+    }
+
+    pub fn add_node(&mut self, node: N) {
+        self.index_of(node);
+    }
+
+    /// Add an edge with weight 1
+    pub fn add_edge(&mut self, from: N, to: N) {
+        self.add_weighted_edge(from, to, 1);
+    }
+
+    pub fn add_weighted_edge(&mut self, from: N, to: N, weight: u64) {
+        let from = self.index_of(from);
+        let to = self.index_of(to);
+        self.successors[from].push((to, weight));
+        self.predecessors[to].push(from);
+    }
+
+    pub fn successors<'a>(&'a self, node: &N) -> impl Iterator<Item = &'a N> {
+        self.weighted_successors(node).map(|(n, _)| n)
+    }
+
+    pub fn weighted_successors<'a>(&'a self, node: &N) -> impl Iterator<Item = (&'a N, u64)> {
+        self.indices
+            .get(node)
+            .into_iter()
+            .flat_map(move |&i| self.successors[i].iter())
+            .map(move |&(j, weight)| (&self.nodes[j], weight))
+    }
+
+    pub fn predecessors<'a>(&'a self, node: &N) -> impl Iterator<Item = &'a N> {
+        self.indices
+            .get(node)
+            .into_iter()
+            .flat_map(move |&i| self.predecessors[i].iter())
+            .map(move |&j| &self.nodes[j])
+    }
+
+    /// Kahn's algorithm where the available node with the smallest key is
+    /// always picked first. Nodes with equal keys are picked in insertion
+    /// order
+    pub fn topological_sort_by_key<K: Ord>(
+        &self,
+        mut key: impl FnMut(&N) -> K,
+    ) -> Result<Vec<N>, CycleError<N>> {
+        let mut in_degrees: Vec<usize> = self.predecessors.iter().map(Vec::len).collect();
+        let mut available: BinaryHeap<_> = (0..self.nodes.len())
+            .filter(|&i| in_degrees[i] == 0)
+            .map(|i| Reverse((key(&self.nodes[i]), i)))
+            .collect();
+
+        let mut out = Vec::with_capacity(self.nodes.len());
+        while let Some(Reverse((_, i))) = available.pop() {
+            out.push(self.nodes[i].clone());
+            for &(j, _) in self.successors[i].iter() {
+                in_degrees[j] -= 1;
+                if in_degrees[j] == 0 {
+                    available.push(Reverse((key(&self.nodes[j]), j)));
+                }
+            }
+        }
+
+        if out.len() == self.nodes.len() {
+            Ok(out)
+        } else {
+            let unsorted = (0..self.nodes.len())
+                .filter(|&i| in_degrees[i] > 0)
+                .map(|i| self.nodes[i].clone())
+                .collect();
+            Err(CycleError { unsorted })
+        }
+    }
+
+    /// Topological sort where the smallest available node is picked first
+    pub fn topological_sort(&self) -> Result<Vec<N>, CycleError<N>>
+    where
+        N: Ord,
+    {
+        self.topological_sort_by_key(|n| n.clone())
+    }
+
+    /// Find a cycle if there is one. The returned nodes are in the order
+    /// they appear on the cycle, starting at an arbitrary node
+    pub fn find_cycle(&self) -> Option<Vec<N>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            New,
+            Active,
+            Done,
+        }
+
+        let mut states = vec![State::New; self.nodes.len()];
+        for root in 0..self.nodes.len() {
+            if states[root] != State::New {
+                continue;
+            }
+
+            // Iterative depth first search where the stack holds the path from
+            // the root, along with how many successors have been visited
+            let mut stack = vec![(root, 0)];
+            states[root] = State::Active;
+            while let Some(&mut (i, ref mut next)) = stack.last_mut() {
+                if let Some(&(j, _)) = self.successors[i].get(*next) {
+                    *next += 1;
+                    match states[j] {
+                        State::New => {
+                            states[j] = State::Active;
+                            stack.push((j, 0));
+                        }
+                        State::Active => {
+                            let start = stack.iter().position(|&(k, _)| k == j).unwrap();
+                            return Some(
+                                stack[start..]
+                                    .iter()
+                                    .map(|&(k, _)| self.nodes[k].clone())
+                                    .collect(),
+                            );
+                        }
+                        State::Done => {}
+                    }
+                } else {
+                    states[i] = State::Done;
+                    stack.pop();
+                }
+            }
+        }
+        None
+    }
+
+    pub fn is_acyclic(&self) -> bool {
+        self.find_cycle().is_none()
+    }
+
+    /// Nodes in breadth first order along with their distance, in number of
+    /// edges, from the start node
+    pub fn bfs(&self, start: &N) -> Vec<(N, usize)> {
+        let mut out = Vec::new();
+        let start = match self.indices.get(start) {
+            Some(&i) => i,
+            None => return out,
+        };
+
+        let mut seen = vec![false; self.nodes.len()];
+        let mut queue = VecDeque::new();
+        seen[start] = true;
+        queue.push_back((start, 0));
+        while let Some((i, depth)) = queue.pop_front() {
+            out.push((self.nodes[i].clone(), depth));
+            for &(j, _) in self.successors[i].iter() {
+                if !seen[j] {
+                    seen[j] = true;
+                    queue.push_back((j, depth + 1));
+                }
+            }
+        }
+        out
+    }
+
+    /// All nodes that can be reached from the start node, including itself
+    pub fn reachable(&self, start: &N) -> HashSet<N> {
+        self.bfs(start).into_iter().map(|(n, _)| n).collect()
+    }
+
+    pub fn is_reachable(&self, from: &N, to: &N) -> bool {
+        self.bfs(from).iter().any(|(n, _)| n == to)
+    }
+
+    /// Cheapest path between two nodes using the edge weights
+    pub fn shortest_path(&self, from: &N, to: &N) -> Option<(u64, Vec<N>)> {
+        self.astar(from, to, |_| 0)
+    }
+
+    /// Cheapest path between two nodes, guided by a heuristic that must never
+    /// overestimate the remaining cost to the goal
+    pub fn astar(
+        &self,
+        from: &N,
+        to: &N,
+        heuristic: impl FnMut(&N) -> u64,
+    ) -> Option<(u64, Vec<N>)> {
+        if !self.contains(from) {
+            return None;
+        }
+        astar(
+            from.clone(),
+            |n| {
+                self.weighted_successors(n)
+                    .map(|(n, w)| (n.clone(), w))
+                    .collect::<Vec<_>>()
+            },
+            heuristic,
+            |n| n == to,
+        )
+    }
+
+    fn index_of(&mut self, node: N) -> usize {
+        if let Some(&i) = self.indices.get(&node) {
+            return i;
+        }
+        let i = self.nodes.len();
+        self.indices.insert(node.clone(), i);
+        self.nodes.push(node);
+        self.successors.push(Vec::new());
+        self.predecessors.push(Vec::new());
+        i
+    }
+}
+
+impl<N: Clone + Eq + Hash> Default for Graph<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A* search over an implicit graph given by a successor function. Returns
+/// the cost of the cheapest path to a goal node along with the path itself,
+/// including the start node
+pub fn astar<N, I>(
+    start: N,
+    mut successors: impl FnMut(&N) -> I,
+    mut heuristic: impl FnMut(&N) -> u64,
+    mut is_goal: impl FnMut(&N) -> bool,
+) -> Option<(u64, Vec<N>)>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = (N, u64)>,
+{
+    // All seen nodes along with the index of their parent and the cheapest
+    // known cost to get there
+    let mut nodes: Vec<(N, usize, u64)> = vec![(start.clone(), 0, 0)];
+    let mut indices: HashMap<N, usize> = HashMap::new();
+    indices.insert(start.clone(), 0);
+
+    let mut queue = BinaryHeap::new();
+    queue.push(Reverse((heuristic(&start), 0u64, 0usize)));
+
+    while let Some(Reverse((_, cost, i))) = queue.pop() {
+        if cost > nodes[i].2 {
+            // We have already found a cheaper way here
+            continue;
+        }
+
+        if is_goal(&nodes[i].0) {
+            let mut path = vec![nodes[i].0.clone()];
+            let mut current = i;
+            while current != 0 {
+                current = nodes[current].1;
+                path.push(nodes[current].0.clone());
+            }
+            path.reverse();
+            return Some((cost, path));
+        }
+
+        for (next, weight) in successors(&nodes[i].0) {
+            let next_cost = cost + weight;
+            let j = match indices.get(&next) {
+                Some(&j) if nodes[j].2 <= next_cost => continue,
+                Some(&j) => {
+                    nodes[j].1 = i;
+                    nodes[j].2 = next_cost;
+                    j
+                }
+                None => {
+                    let j = nodes.len();
+                    indices.insert(next.clone(), j);
+                    nodes.push((next, i, next_cost));
+                    j
+                }
+            };
+            queue.push(Reverse((next_cost + heuristic(&nodes[j].0), next_cost, j)));
+        }
+    }
+    None
+}
+
+/// Dijkstra's algorithm over an implicit graph, see `astar`
+pub fn dijkstra<N, I>(
+    start: N,
+    successors: impl FnMut(&N) -> I,
+    is_goal: impl FnMut(&N) -> bool,
+) -> Option<(u64, Vec<N>)>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = (N, u64)>,
+{
+    astar(start, successors, |_| 0, is_goal)
+}
+
+#[cfg(test)]
+fn example_graph() -> Graph<char> {
+    let mut graph = Graph::new();
+    for &(a, b) in [
+        ('C', 'A'),
+        ('C', 'F'),
+        ('A', 'B'),
+        ('A', 'D'),
+        ('B', 'E'),
+        ('D', 'E'),
+        ('F', 'E'),
+    ]
+    .iter()
+    {
+        graph.add_edge(a, b);
+    }
+    graph
+}
+
+#[test]
+fn test_topological_sort() {
+    let graph = example_graph();
+    assert_eq!(
+        graph
+            .topological_sort()
+            .unwrap()
+            .into_iter()
+            .collect::<String>(),
+        "CABDFE"
+    );
+    assert_eq!(
+        graph
+            .topological_sort_by_key(|&c| Reverse(c))
+            .unwrap()
+            .into_iter()
+            .collect::<String>(),
+        "CFADBE"
+    );
+
+    let mut cyclic = example_graph();
+    cyclic.add_edge('E', 'A');
+    let mut unsorted = cyclic.topological_sort().unwrap_err().unsorted;
+    unsorted.sort();
+    assert_eq!(unsorted, vec!['A', 'B', 'D', 'E']);
+}
+
+#[test]
+fn test_find_cycle() {
+    let mut graph = example_graph();
+    assert_eq!(graph.find_cycle(), None);
+    assert!(graph.is_acyclic());
+
+    graph.add_edge('E', 'A');
+    assert_eq!(graph.find_cycle(), Some(vec!['A', 'B', 'E']));
+
+    let mut graph = Graph::new();
+    graph.add_edge(1, 1);
+    assert_eq!(graph.find_cycle(), Some(vec![1]));
+}
+
+#[test]
+fn test_bfs_reachable() {
+    let graph = example_graph();
+    assert_eq!(
+        graph.bfs(&'A'),
+        vec![('A', 0), ('B', 1), ('D', 1), ('E', 2)]
+    );
+    assert!(graph.is_reachable(&'C', &'E'));
+    assert!(!graph.is_reachable(&'F', &'A'));
+    assert_eq!(graph.reachable(&'F'), vec!['F', 'E'].into_iter().collect());
+    assert_eq!(graph.predecessors(&'E').collect::<String>(), "BDF");
+}
+
+#[test]
+fn test_shortest_path() {
+    let mut graph = Graph::new();
+    graph.add_weighted_edge("a", "b", 7);
+    graph.add_weighted_edge("a", "c", 2);
+    graph.add_weighted_edge("c", "b", 3);
+    graph.add_weighted_edge("b", "d", 1);
+    assert_eq!(
+        graph.shortest_path(&"a", &"d"),
+        Some((6, vec!["a", "c", "b", "d"]))
+    );
+    assert_eq!(graph.shortest_path(&"d", &"a"), None);
+    assert_eq!(graph.shortest_path(&"x", &"a"), None);
+}
+
+#[test]
+fn test_astar_implicit() {
+    // Walk on a number line where each step costs 1, guided by the distance
+    // to the goal
+    let (cost, path) = astar(
+        0i32,
+        |&n| vec![(n - 1, 1), (n + 1, 1)],
+        |&n| (10 - n).unsigned_abs() as u64,
+        |&n| n == 10,
+    )
+    .unwrap();
+    assert_eq!(cost, 10);
+    assert_eq!(path, (0..=10).collect::<Vec<_>>());
+
+    assert_eq!(
+        dijkstra(1u64, |&n| vec![(n * 2, 1), (n + 1, 1)], |&n| n == 9),
+        Some((4, vec![1, 2, 4, 8, 9]))
+    );
+}
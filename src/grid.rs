@@ -0,0 +1,370 @@
+use crate::geom::Point2;
+use crate::parse::{Block, ParseError};
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+/// Dense two dimensional grid stored in row major order. `x` is the column
+/// and `y` the row, with `(0, 0)` in the top left corner
+#[derive(Clone, Eq, Hash, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize) -> Self
+    where
+        T: Clone + Default,
+    {
+        Self::filled(width, height, T::default())
+    }
+
+    pub fn filled(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(usize, usize) -> T) -> Self {
+        let mut cells = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(f(x, y));
+            }
+        }
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Parse character art where each character is turned into a cell. All
+    /// rows must have the same width
+    pub fn parse_with(s: &str, mut f: impl FnMut(char) -> Option<T>) -> Result<Self, ParseError> {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = Vec::new();
+
+        for line in Block::new(s).lines() {
+            let mut row_width = 0;
+            for (column, value) in line.text.chars().enumerate() {
+                let cell = f(value)
+                    .ok_or_else(|| line.error(ParseError::InvalidChar { column, value }))?;
+                cells.push(cell);
+                row_width += 1;
+            }
+
+            if *width.get_or_insert(row_width) != row_width {
+                return Err(line.error(ParseError::NoMatch(line.text.to_owned())));
+            }
+            height += 1;
+        }
+
+        Ok(Self {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if self.contains(x, y) {
+            self.cells.get(x + y * self.width)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if self.contains(x, y) {
+            self.cells.get_mut(x + y * self.width)
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    /// All coordinates in row major order
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.width * self.height).map(move |i| (i % width, i / width))
+    }
+
+    pub fn enumerate(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        assert!(y < self.height, "Row {} is out of bounds", y);
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+        assert!(y < self.height, "Row {} is out of bounds", y);
+        &mut self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    /// All rows from the top. A grid without width still has `height` rows,
+    /// they are just empty
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(move |y| self.row(y))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "Column {} is out of bounds", x);
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    /// Orthogonal neighbours that are within the grid
+    pub fn neighbours4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        self.offsets(x, y, &[(0, -1), (-1, 0), (1, 0), (0, 1)])
+    }
+
+    /// Orthogonal and diagonal neighbours that are within the grid
+    pub fn neighbours8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        self.offsets(
+            x,
+            y,
+            &[
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ],
+        )
+    }
+
+    fn offsets(
+        &self,
+        x: usize,
+        y: usize,
+        offsets: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = (self.width, self.height);
+        offsets.iter().filter_map(move |&(dx, dy)| {
+            let nx = x.checked_add_signed(dx)?;
+            let ny = y.checked_add_signed(dy)?;
+            if nx < width && ny < height {
+                Some((nx, ny))
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Render the grid as text, one line per row
+    pub fn render(&self, mut f: impl FnMut(&T) -> char) -> String {
+        let mut out = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rows() {
+            out.extend(row.iter().map(&mut f));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl Grid<bool> {
+    /// Smallest grid that covers all points, with the cells of the points
+    /// set. Also returns the point that the top left corner corresponds to
+    pub fn from_points(points: impl IntoIterator<Item = Point2>) -> (Point2, Self) {
+        let points: Vec<Point2> = points.into_iter().collect();
+        let (min, max) = match Point2::bounds(points.iter().cloned()) {
+            Some(bounds) => bounds,
+            None => return (Point2::origin(), Self::new(0, 0)),
+        };
+
+        let width = (max.x - min.x + 1) as usize;
+        let height = (max.y - min.y + 1) as usize;
+        let mut grid = Self::new(width, height);
+        for p in points {
+            grid[((p.x - min.x) as usize, (p.y - min.y) as usize)] = true;
+        }
+        (min, grid)
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        match self.get(x, y) {
+            Some(cell) => cell,
+            None => panic!(
+                "Position ({}, {}) is outside of {}x{} grid",
+                x, y, self.width, self.height
+            ),
+        }
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        match self.get_mut(x, y) {
+            Some(cell) => cell,
+            None => panic!(
+                "Position ({}, {}) is outside of {}x{} grid",
+                x, y, width, height
+            ),
+        }
+    }
+}
+
+impl FromStr for Grid<char> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, Some)
+    }
+}
+
+impl fmt::Display for Grid<char> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(|c| *c))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Grid<{}x{}>", self.width, self.height)?;
+        f.debug_list().entries(self.rows()).finish()
+    }
+}
+
+#[test]
+fn test_parse_render() {
+    let grid: Grid<char> = "#..\n.#.\n".parse().unwrap();
+    assert_eq!((grid.width(), grid.height()), (3, 2));
+    assert_eq!(grid[(1, 1)], '#');
+    assert_eq!(grid.get(3, 0), None);
+    assert_eq!(grid.to_string(), "#..\n.#.\n");
+
+    let bools = Grid::parse_with("#.\n.#", |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })
+    .unwrap();
+    assert_eq!(bools.render(|&b| if b { 'x' } else { ' ' }), "x \n x\n");
+
+    assert_eq!(
+        "#..\n.#\n".parse::<Grid<char>>(),
+        Err(ParseError::AtLine {
+            line: 2,
+            error: Box::new(ParseError::NoMatch(".#".to_owned()))
+        })
+    );
+    assert_eq!(
+        Grid::parse_with("..\n.x", |c| if c == '.' { Some(()) } else { None }),
+        Err(ParseError::AtLine {
+            line: 2,
+            error: Box::new(ParseError::InvalidChar {
+                column: 1,
+                value: 'x'
+            })
+        })
+    );
+}
+
+#[test]
+fn test_views() {
+    let grid = Grid::from_fn(3, 2, |x, y| x + 10 * y);
+    assert_eq!(grid.row(1), &[10, 11, 12]);
+    assert_eq!(grid.column(2).cloned().collect::<Vec<_>>(), vec![2, 12]);
+    assert_eq!(
+        grid.rows().collect::<Vec<_>>(),
+        vec![&[0, 1, 2][..], &[10, 11, 12][..]]
+    );
+    assert_eq!(
+        grid.positions().collect::<Vec<_>>(),
+        vec![(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]
+    );
+    assert_eq!(grid.map(|v| v * 2)[(2, 1)], 24);
+}
+
+#[test]
+fn test_neighbours() {
+    let grid: Grid<u8> = Grid::new(3, 3);
+    assert_eq!(
+        grid.neighbours4(0, 0).collect::<Vec<_>>(),
+        vec![(1, 0), (0, 1)]
+    );
+    assert_eq!(grid.neighbours4(1, 1).count(), 4);
+    assert_eq!(
+        grid.neighbours8(2, 2).collect::<Vec<_>>(),
+        vec![(1, 1), (2, 1), (1, 2)]
+    );
+    assert_eq!(grid.neighbours8(1, 1).count(), 8);
+}
+
+#[test]
+#[should_panic]
+fn test_index_out_of_bounds() {
+    let grid: Grid<u8> = Grid::new(3, 3);
+    let _ = grid[(3, 0)];
+}
+
+#[test]
+#[should_panic(expected = "Row 3 is out of bounds")]
+fn test_row_out_of_bounds() {
+    let grid: Grid<u8> = Grid::new(3, 3);
+    let _ = grid.row(3);
+}
+
+#[test]
+fn test_zero_width() {
+    let grid: Grid<u8> = Grid::new(0, 2);
+    assert_eq!(grid.height(), 2);
+    assert_eq!(grid.rows().count(), 2);
+    assert_eq!(grid.render(|_| '#'), "\n\n");
+}
+
+#[test]
+fn test_from_points() {
+    let points = vec![Point2::new(-1, 2), Point2::new(1, 3), Point2::new(-1, 2)];
+    let (origin, grid) = Grid::from_points(points);
+    assert_eq!(origin, Point2::new(-1, 2));
+    assert_eq!(grid.render(|&b| if b { '#' } else { '.' }), "#..\n..#\n");
+
+    let (_, empty) = Grid::from_points(Vec::new());
+    assert_eq!((empty.width(), empty.height()), (0, 0));
+}
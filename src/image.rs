@@ -0,0 +1,209 @@
+use crate::grid::Grid;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Colour with eight bits per channel
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    /// Linear interpolation between two colours, where `t` is between 0 and 1
+    pub fn lerp(self, other: Self, t: f64) -> Self {
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Rgb(
+            mix(self.0, other.0),
+            mix(self.1, other.1),
+            mix(self.2, other.2),
+        )
+    }
+}
+
+/// Pixel type that can be written as a binary Netpbm image
+pub trait Pixel: Copy {
+    /// Magic number that starts the file
+    const MAGIC: &'static str;
+    const EXTENSION: &'static str;
+
+    fn write_bytes(&self, out: &mut Vec<u8>);
+}
+
+/// Grayscale, written as PGM
+impl Pixel for u8 {
+    const MAGIC: &'static str = "P5";
+    const EXTENSION: &'static str = "pgm";
+
+    fn write_bytes(&self, out: &mut Vec<u8>) {
+        out.push(*self);
+    }
+}
+
+/// Colour, written as PPM
+impl Pixel for Rgb {
+    const MAGIC: &'static str = "P6";
+    const EXTENSION: &'static str = "ppm";
+
+    fn write_bytes(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&[self.0, self.1, self.2]);
+    }
+}
+
+/// Write the grid as a binary PGM or PPM image depending on the pixel type
+pub fn write<P: Pixel>(image: &Grid<P>, mut out: impl Write) -> io::Result<()> {
+    let mut data =
+        format!("{}\n{} {}\n255\n", P::MAGIC, image.width(), image.height()).into_bytes();
+    for pixel in image.iter() {
+        pixel.write_bytes(&mut data);
+    }
+    out.write_all(&data)?;
+    out.flush()
+}
+
+pub fn save<P: Pixel>(image: &Grid<P>, path: impl AsRef<Path>) -> io::Result<()> {
+    write(image, BufWriter::new(File::create(path)?))
+}
+
+/// Gradient that maps values between 0 and 1 to colours
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ColorMap {
+    /// Black to white
+    Gray,
+    /// Black through red and yellow to white
+    Heat,
+    /// Blue through white to red, for values where the middle means zero
+    Diverging,
+}
+
+impl ColorMap {
+    fn stops(self) -> &'static [Rgb] {
+        match self {
+            ColorMap::Gray => &[Rgb(0, 0, 0), Rgb(255, 255, 255)],
+            ColorMap::Heat => &[
+                Rgb(0, 0, 0),
+                Rgb(200, 0, 0),
+                Rgb(255, 210, 0),
+                Rgb(255, 255, 255),
+            ],
+            ColorMap::Diverging => &[Rgb(40, 60, 200), Rgb(255, 255, 255), Rgb(200, 30, 30)],
+        }
+    }
+
+    /// Colour at `t`, which is clamped to between 0 and 1
+    pub fn color(self, t: f64) -> Rgb {
+        let stops = self.stops();
+        let pos = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+        let i = (pos.floor() as usize).min(stops.len() - 2);
+        stops[i].lerp(stops[i + 1], pos - i as f64)
+    }
+}
+
+/// Colour every cell by its value. Values are scaled so that the smallest
+/// one gets the first colour of the map and the largest one the last. For
+/// diverging maps the scale is centered on zero instead
+pub fn heatmap<T>(grid: &Grid<T>, map: ColorMap, mut value: impl FnMut(&T) -> f64) -> Grid<Rgb> {
+    let values = grid.map(&mut value);
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let (low, high) = match map {
+        ColorMap::Diverging => {
+            let limit = min.abs().max(max.abs());
+            (-limit, limit)
+        }
+        _ => (min, max),
+    };
+
+    values.map(|&v| {
+        if high > low {
+            map.color((v - low) / (high - low))
+        } else {
+            map.color(0.5)
+        }
+    })
+}
+
+/// Writes images to numbered files in a directory, for turning into a video
+/// or flipping through
+pub struct ImageSequence {
+    dir: PathBuf,
+    prefix: String,
+    len: usize,
+}
+
+impl ImageSequence {
+    /// Files are named by the prefix followed by the frame number. The
+    /// directory is created if it doesn't exist
+    pub fn new(dir: impl Into<PathBuf>, prefix: &str) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            prefix: prefix.to_owned(),
+            len: 0,
+        })
+    }
+
+    /// Number of images written so far
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Write the next image and return its path
+    pub fn push<P: Pixel>(&mut self, image: &Grid<P>) -> io::Result<PathBuf> {
+        let name = format!("{}{:05}.{}", self.prefix, self.len, P::EXTENSION);
+        let path = self.dir.join(name);
+        save(image, &path)?;
+        self.len += 1;
+        Ok(path)
+    }
+}
+
+#[test]
+fn test_write() {
+    let mut out = Vec::new();
+    write(&Grid::from_fn(3, 2, |x, y| (x * 10 + y) as u8), &mut out).unwrap();
+    assert_eq!(out, b"P5\n3 2\n255\n\x00\x0a\x14\x01\x0b\x15");
+
+    let mut out = Vec::new();
+    write(&Grid::filled(1, 2, Rgb(1, 2, 3)), &mut out).unwrap();
+    assert_eq!(out, b"P6\n1 2\n255\n\x01\x02\x03\x01\x02\x03");
+}
+
+#[test]
+fn test_color_maps() {
+    assert_eq!(ColorMap::Gray.color(0.5), Rgb(128, 128, 128));
+    assert_eq!(ColorMap::Gray.color(-1.0), Rgb(0, 0, 0));
+    assert_eq!(ColorMap::Heat.color(1.0), Rgb(255, 255, 255));
+    assert_eq!(ColorMap::Diverging.color(0.5), Rgb(255, 255, 255));
+
+    let grid = Grid::from_fn(3, 1, |x, _| x as i32 - 1);
+    let gray = heatmap(&grid, ColorMap::Gray, |&v| v as f64);
+    assert_eq!(
+        gray.row(0),
+        &[Rgb(0, 0, 0), Rgb(128, 128, 128), Rgb(255, 255, 255)]
+    );
+
+    // Zero stays white even though the values aren't symmetric
+    let grid = Grid::from_fn(2, 1, |x, _| x as f64 * 4.0);
+    let diverging = heatmap(&grid, ColorMap::Diverging, |&v| v);
+    assert_eq!(diverging.row(0), &[Rgb(255, 255, 255), Rgb(200, 30, 30)]);
+
+    let flat = heatmap(&Grid::filled(2, 2, 7), ColorMap::Heat, |&v| v as f64);
+    assert!(flat.iter().all(|&c| c == ColorMap::Heat.color(0.5)));
+}
+
+#[test]
+fn test_sequence() {
+    let dir = std::env::temp_dir().join(format!("aoc-image-{}", std::process::id()));
+    let mut sequence = ImageSequence::new(&dir, "gen").unwrap();
+    let first = sequence.push(&Grid::filled(2, 2, 0u8)).unwrap();
+    let second = sequence.push(&Grid::filled(2, 2, Rgb(0, 0, 0))).unwrap();
+    assert_eq!(sequence.len(), 2);
+    assert_eq!(first, dir.join("gen00000.pgm"));
+    assert_eq!(second, dir.join("gen00001.ppm"));
+    assert_eq!(fs::read(&first).unwrap(), b"P5\n2 2\n255\n\0\0\0\0");
+    fs::remove_dir_all(&dir).unwrap();
+}
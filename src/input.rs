@@ -0,0 +1,398 @@
+use crate::cli::Args;
+use crate::json::Value;
+use crate::parse::{Block, ParseError};
+use std::path::Path;
+use std::str::FromStr;
+
+/// How the puzzle input is represented. Besides the puzzle text, inputs that
+/// consist of a list of items can be given as a JSON array of objects or as
+/// CSV with a header, where each object or row is one item
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InputFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+impl InputFormat {
+    /// Format given by the file extension, defaulting to puzzle text
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        let extension = path.as_ref().extension().and_then(|e| e.to_str());
+        match extension.map(str::to_ascii_lowercase).as_deref() {
+            Some("json") => InputFormat::Json,
+            Some("csv") => InputFormat::Csv,
+            _ => InputFormat::Text,
+        }
+    }
+
+    /// Format given by `--input-format`, otherwise by the extension of the
+    /// input path
+    pub fn from_args(args: &Args) -> Self {
+        match (args.input_format, args.positional.first()) {
+            (Some(format), _) => format,
+            (None, Some(path)) => Self::from_path(path),
+            (None, None) => InputFormat::Text,
+        }
+    }
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(InputFormat::Text),
+            "json" => Ok(InputFormat::Json),
+            "csv" => Ok(InputFormat::Csv),
+            _ => Err(format!(
+                "Unknown input format {:?}, expected text, json or csv",
+                s
+            )),
+        }
+    }
+}
+
+/// Where a record came from, for error messages
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Location {
+    Line(usize),
+    Index(usize),
+}
+
+/// Named fields of one item of structured input. Values from CSV are always
+/// strings, or null for empty cells, so fields are converted from their text
+/// when read
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    location: Location,
+    fields: Vec<(String, Value)>,
+}
+
+impl Record {
+    /// Value of the field, where null counts as missing
+    pub fn value(&self, key: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+            .filter(|v| **v != Value::Null)
+    }
+
+    pub fn get<T: FromStr>(&self, key: &str) -> Result<T, ParseError> {
+        self.get_opt(key)?
+            .ok_or_else(|| ParseError::MissingField(key.to_owned()))
+    }
+
+    pub fn get_opt<T: FromStr>(&self, key: &str) -> Result<Option<T>, ParseError> {
+        self.value(key).map(|v| convert(key, v)).transpose()
+    }
+
+    /// A JSON array, or a string of values separated by commas or spaces,
+    /// optionally in brackets like `[3, 2, 1, 1]`
+    pub fn get_list<T: FromStr>(&self, key: &str) -> Result<Vec<T>, ParseError> {
+        match self.value(key) {
+            Some(Value::Array(values)) => values.iter().map(|v| convert(key, v)).collect(),
+            Some(Value::String(s)) => s
+                .split(|c: char| c == ',' || c == '[' || c == ']' || c.is_whitespace())
+                .filter(|item| !item.is_empty())
+                .map(|item| convert(key, &Value::from(item)))
+                .collect(),
+            Some(value) => Err(ParseError::InvalidField {
+                field: key.to_owned(),
+                value: value.to_string(),
+            }),
+            None => Err(ParseError::MissingField(key.to_owned())),
+        }
+    }
+
+    /// Wrap an error with the line or index of this record
+    pub fn error(&self, error: ParseError) -> ParseError {
+        match self.location {
+            Location::Line(line) => ParseError::AtLine {
+                line,
+                error: Box::new(error),
+            },
+            Location::Index(index) => ParseError::AtRecord {
+                index,
+                error: Box::new(error),
+            },
+        }
+    }
+}
+
+fn convert<T: FromStr>(key: &str, value: &Value) -> Result<T, ParseError> {
+    let text = match value {
+        Value::String(s) => s.trim().to_owned(),
+        value => value.to_string(),
+    };
+    text.parse().map_err(|_| ParseError::InvalidField {
+        field: key.to_owned(),
+        value: text,
+    })
+}
+
+/// Records from a JSON array of objects
+pub fn json_records(text: &str) -> Result<Vec<Record>, ParseError> {
+    let values = match text.parse()? {
+        Value::Array(values) => values,
+        value => return Err(ParseError::NoMatch(value.to_string())),
+    };
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| match value {
+            Value::Object(fields) => Ok(Record {
+                location: Location::Index(i + 1),
+                fields,
+            }),
+            value => Err(ParseError::AtRecord {
+                index: i + 1,
+                error: Box::new(ParseError::NoMatch(value.to_string())),
+            }),
+        })
+        .collect()
+}
+
+/// Cells of a CSV row along with the line the row starts at
+type CsvRow = (usize, Vec<Option<String>>);
+
+/// Split CSV into rows of cells. Quoted cells may contain commas, newlines
+/// and quotes written as `""`, and whitespace around the quotes is ignored.
+/// Empty unquoted cells are None
+fn csv_rows(text: &str) -> Result<Vec<CsvRow>, ParseError> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    cell.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if cell.trim().is_empty() && !quoted => {
+                cell.clear();
+                quoted = true;
+                in_quotes = true;
+            }
+            '"' => {
+                return Err(ParseError::AtLine {
+                    line,
+                    error: Box::new(ParseError::NoMatch(format!("{}\"", cell))),
+                })
+            }
+            '\n' if in_quotes => {
+                line += 1;
+                cell.push(c);
+            }
+            c if in_quotes => cell.push(c),
+            ',' | '\n' => {
+                let value = std::mem::take(&mut cell);
+                row.push(if quoted || !value.trim().is_empty() {
+                    Some(value)
+                } else {
+                    None
+                });
+                quoted = false;
+                if c == '\n' {
+                    rows.push((row_line, std::mem::take(&mut row)));
+                    line += 1;
+                    row_line = line;
+                }
+            }
+            '\r' => {}
+            ' ' | '\t' if quoted => {}
+            c => cell.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(ParseError::AtLine {
+            line: row_line,
+            error: Box::new(ParseError::UnexpectedEnd),
+        });
+    }
+    if quoted || !cell.is_empty() || !row.is_empty() {
+        row.push(Some(cell).filter(|cell| quoted || !cell.trim().is_empty()));
+        rows.push((row_line, row));
+    }
+
+    // Blank lines are a single empty cell
+    rows.retain(|(_, row)| row.as_slice() != [None]);
+    Ok(rows)
+}
+
+/// Records from CSV where the first row names the fields
+pub fn csv_records(text: &str) -> Result<Vec<Record>, ParseError> {
+    let mut rows = csv_rows(text)?.into_iter();
+    let header: Vec<String> = match rows.next() {
+        Some((_, header)) => header
+            .into_iter()
+            .map(|name| name.unwrap_or_default().trim().to_owned())
+            .collect(),
+        None => return Ok(Vec::new()),
+    };
+
+    rows.map(|(line, cells)| {
+        if cells.len() > header.len() {
+            let extra = cells[header.len()].clone().unwrap_or_default();
+            return Err(ParseError::AtLine {
+                line,
+                error: Box::new(ParseError::NoMatch(extra)),
+            });
+        }
+        // Missing cells at the end of a row are null, like empty ones
+        let fields = header
+            .iter()
+            .cloned()
+            .zip(
+                cells
+                    .into_iter()
+                    .map(Value::from)
+                    .chain(std::iter::repeat(Value::Null)),
+            )
+            .collect();
+        Ok(Record {
+            location: Location::Line(line),
+            fields,
+        })
+    })
+    .collect()
+}
+
+/// Records of JSON or CSV input. Puzzle text has no records
+pub fn records(text: &str, format: InputFormat) -> Result<Vec<Record>, ParseError> {
+    match format {
+        InputFormat::Json => json_records(text),
+        InputFormat::Csv => csv_records(text),
+        InputFormat::Text => Err(ParseError::NoMatch(
+            "puzzle text has no records, expected JSON or CSV".to_owned(),
+        )),
+    }
+}
+
+/// Parse each non-blank line of puzzle text, or each record of JSON or CSV
+/// input, into the same kind of item
+pub fn parse_items<T>(
+    text: &str,
+    format: InputFormat,
+    from_line: impl Fn(&str) -> Result<T, ParseError>,
+    from_record: impl Fn(&Record) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    match format {
+        InputFormat::Text => Block::new(text)
+            .lines()
+            .map(|line| from_line(line.text).map_err(|e| line.error(e)))
+            .collect(),
+        format => records(text, format)?
+            .iter()
+            .map(|record| from_record(record).map_err(|e| record.error(e)))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+fn parse_pair(s: &str) -> Result<(u32, char), ParseError> {
+    let mut parts = s.split(' ');
+    match (parts.next(), parts.next()) {
+        (Some(n), Some(c)) => Ok((
+            n.parse().map_err(|_| ParseError::NoMatch(s.to_owned()))?,
+            c.parse().map_err(|_| ParseError::NoMatch(s.to_owned()))?,
+        )),
+        _ => Err(ParseError::NoMatch(s.to_owned())),
+    }
+}
+
+#[cfg(test)]
+fn pair_from_record(record: &Record) -> Result<(u32, char), ParseError> {
+    Ok((record.get("n")?, record.get("c")?))
+}
+
+#[test]
+fn test_format() {
+    assert_eq!(InputFormat::from_path("data/day3.txt"), InputFormat::Text);
+    assert_eq!(InputFormat::from_path("claims.JSON"), InputFormat::Json);
+    assert_eq!(InputFormat::from_path("claims.csv"), InputFormat::Csv);
+    assert_eq!("csv".parse(), Ok(InputFormat::Csv));
+    assert!("yaml".parse::<InputFormat>().is_err());
+}
+
+#[test]
+fn test_parse_items() {
+    let expected = Ok(vec![(1, 'a'), (22, ',')]);
+    let parse = |text, format| parse_items(text, format, parse_pair, pair_from_record);
+
+    assert_eq!(parse("1 a\n\n22 ,\n", InputFormat::Text), expected);
+    assert_eq!(
+        parse(
+            r#"[{"n": 1, "c": "a"}, {"c": ",", "n": "22"}]"#,
+            InputFormat::Json
+        ),
+        expected
+    );
+    assert_eq!(
+        parse("n,c\r\n1,a\r\n\r\n22,\",\"\r\n", InputFormat::Csv),
+        expected
+    );
+}
+
+#[test]
+fn test_csv() {
+    let records = csv_records("name,list,note\n\"a \"\"b\"\"\",\"1, 2\n3\",\nc\n").unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].get::<String>("name"), Ok("a \"b\"".to_owned()));
+    assert_eq!(records[0].get_list::<u8>("list"), Ok(vec![1, 2, 3]));
+    assert_eq!(records[0].get_opt::<String>("note"), Ok(None));
+    assert_eq!(records[1].get_opt::<String>("list"), Ok(None));
+    assert_eq!(
+        records[1].error(ParseError::UnexpectedEnd).to_string(),
+        "line 4: unexpected end of input"
+    );
+
+    assert_eq!(
+        csv_records("a\n1,2\n"),
+        Err(ParseError::AtLine {
+            line: 2,
+            error: Box::new(ParseError::NoMatch("2".to_owned()))
+        })
+    );
+    let records = csv_records("a,b\n1, \"2, 3\" \n").unwrap();
+    assert_eq!(records[0].get::<String>("b"), Ok("2, 3".to_owned()));
+    assert!(csv_records("a\n\"1\n").is_err());
+    assert!(csv_records("a\n1\"\n").is_err());
+}
+
+#[test]
+fn test_errors() {
+    let parse = |text, format| parse_items(text, format, parse_pair, pair_from_record);
+    assert_eq!(
+        parse(r#"[{"n": 1, "c": "a"}, {"n": 2}]"#, InputFormat::Json),
+        Err(ParseError::AtRecord {
+            index: 2,
+            error: Box::new(ParseError::MissingField("c".to_owned()))
+        })
+    );
+    assert_eq!(
+        parse("n,c\n1,a\n-1,b\n", InputFormat::Csv),
+        Err(ParseError::AtLine {
+            line: 3,
+            error: Box::new(ParseError::InvalidField {
+                field: "n".to_owned(),
+                value: "-1".to_owned()
+            })
+        })
+    );
+    assert_eq!(
+        parse(r#"{"n": 1}"#, InputFormat::Json),
+        Err(ParseError::NoMatch(r#"{"n":1}"#.to_owned()))
+    );
+    assert!(records("1 a", InputFormat::Text).is_err());
+}
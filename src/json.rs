@@ -0,0 +1,428 @@
+use crate::parse::ParseError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Minimal JSON value, used for writing and reading NDJSON records.
+///
+/// Objects keep their insertion order so that output is stable between runs
+/// and can be diffed line by line.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn object<K, I>(fields: I) -> Self
+    where
+        K: Into<String>,
+        I: IntoIterator<Item = (K, Value)>,
+    {
+        Value::Object(fields.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    /// Field of an object. The first one wins if the key is repeated
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i128> {
+        match self {
+            Value::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+}
+
+fn write_str(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(x) if x.is_finite() => write!(f, "{:?}", x),
+            Value::Float(_) => write!(f, "null"),
+            Value::String(s) => write_str(f, s),
+            Value::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Value::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_str(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Recursive descent parser over the characters of the input. Columns in
+/// errors are character indices
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn next(&mut self) -> Result<char, ParseError> {
+        let c = self.peek().ok_or(ParseError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(c)
+    }
+
+    fn invalid(&self, column: usize) -> ParseError {
+        match self.chars.get(column) {
+            Some(&value) => ParseError::InvalidChar { column, value },
+            None => ParseError::UnexpectedEnd,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.next()? == expected {
+            Ok(())
+        } else {
+            Err(self.invalid(self.pos - 1))
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Value) -> Result<Value, ParseError> {
+        for expected in word.chars() {
+            if self.next()? != expected {
+                return Err(self.invalid(self.pos - 1));
+            }
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        self.skip_whitespace();
+        match self.peek().ok_or(ParseError::UnexpectedEnd)? {
+            'n' => self.keyword("null", Value::Null),
+            't' => self.keyword("true", Value::Bool(true)),
+            'f' => self.keyword("false", Value::Bool(false)),
+            '"' => self.string().map(Value::String),
+            '[' => self.array(),
+            '{' => self.object(),
+            '-' | '0'..='9' => self.number(),
+            _ => Err(self.invalid(self.pos)),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        let mut is_float = false;
+        while let Some(c) = self.peek() {
+            match c {
+                '0'..='9' | '-' | '+' => {}
+                '.' | 'e' | 'E' => is_float = true,
+                _ => break,
+            }
+            self.pos += 1;
+        }
+
+        let text: String = self.chars[start..self.pos].iter().collect();
+        let value = if is_float {
+            text.parse().map(Value::Float).ok()
+        } else {
+            text.parse().map(Value::Int).ok()
+        };
+        value.ok_or(ParseError::InvalidValue {
+            group: start,
+            value: text,
+        })
+    }
+
+    fn hex4(&mut self) -> Result<u32, ParseError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.next()?.to_digit(16);
+            code = code * 16 + digit.ok_or_else(|| self.invalid(self.pos - 1))?;
+        }
+        Ok(code)
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.next()? {
+                '"' => return Ok(out),
+                '\\' => {
+                    let escape_start = self.pos - 1;
+                    let c = match self.next()? {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => {
+                            let mut code = self.hex4()?;
+                            // Characters outside the basic plane are escaped
+                            // as a surrogate pair
+                            if (0xd800..0xdc00).contains(&code) {
+                                self.expect('\\')?;
+                                self.expect('u')?;
+                                let low = self.hex4()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(self.invalid(escape_start));
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            char::from_u32(code).ok_or_else(|| self.invalid(escape_start))?
+                        }
+                        _ => return Err(self.invalid(self.pos - 1)),
+                    };
+                    out.push(c);
+                }
+                c if (c as u32) < 0x20 => return Err(self.invalid(self.pos - 1)),
+                c => out.push(c),
+            }
+        }
+    }
+
+    /// Comma separated items between the given delimiters
+    fn items(
+        &mut self,
+        open: char,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<(), ParseError>,
+    ) -> Result<(), ParseError> {
+        self.expect(open)?;
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            item(self)?;
+            self.skip_whitespace();
+            match self.next()? {
+                ',' => continue,
+                c if c == close => return Ok(()),
+                _ => return Err(self.invalid(self.pos - 1)),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, ParseError> {
+        let mut values = Vec::new();
+        self.items('[', ']', |p| {
+            values.push(p.value()?);
+            Ok(())
+        })?;
+        Ok(Value::Array(values))
+    }
+
+    fn object(&mut self) -> Result<Value, ParseError> {
+        let mut fields = Vec::new();
+        self.items('{', '}', |p| {
+            p.skip_whitespace();
+            let key = p.string()?;
+            p.expect(':')?;
+            fields.push((key, p.value()?));
+            Ok(())
+        })?;
+        Ok(Value::Object(fields))
+    }
+}
+
+impl FromStr for Value {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.chars.len() {
+            return Err(parser.invalid(parser.pos));
+        }
+        Ok(value)
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Value {
+                fn from(value: $t) -> Self {
+                    Value::Int(value as i128)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<char> for Value {
+    fn from(value: char) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(values: Vec<T>) -> Self {
+        Value::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+#[test]
+fn test_display() {
+    let value = Value::object(vec![
+        ("gen", Value::from(3)),
+        ("pots", Value::from("#..#\t\"x\"")),
+        ("sums", Value::from(vec![-1i64, 2])),
+        ("done", Value::from(true)),
+        ("eta", Value::from(None::<u64>)),
+    ]);
+    assert_eq!(
+        value.to_string(),
+        r##"{"gen":3,"pots":"#..#\t\"x\"","sums":[-1,2],"done":true,"eta":null}"##
+    );
+}
+
+#[test]
+fn test_parse() {
+    let text = r##" {"gen": 3, "pots": "#..#\t\"x\"", "sums": [-1, 2.5e1, []],
+        "done": true, "eta": null, "u": "\u00e9\ud83c\udf84"} "##;
+    let value: Value = text.parse().unwrap();
+    assert_eq!(
+        value,
+        Value::object(vec![
+            ("gen", Value::from(3)),
+            ("pots", Value::from("#..#\t\"x\"")),
+            (
+                "sums",
+                Value::Array(vec![
+                    Value::Int(-1),
+                    Value::Float(25.0),
+                    Value::Array(vec![])
+                ])
+            ),
+            ("done", Value::from(true)),
+            ("eta", Value::Null),
+            ("u", Value::from("\u{e9}\u{1f384}")),
+        ])
+    );
+    assert_eq!(value.get("gen").and_then(Value::as_int), Some(3));
+    assert_eq!(
+        value.get("pots").and_then(Value::as_str),
+        Some("#..#\t\"x\"")
+    );
+    assert_eq!(value.get("missing"), None);
+
+    // Round trip through the writer
+    assert_eq!(value.to_string().parse::<Value>(), Ok(value));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!("[1, 2".parse::<Value>(), Err(ParseError::UnexpectedEnd));
+    assert_eq!(
+        "[1 2]".parse::<Value>(),
+        Err(ParseError::InvalidChar {
+            column: 3,
+            value: '2'
+        })
+    );
+    assert_eq!(
+        "{\"a\": tru}".parse::<Value>(),
+        Err(ParseError::InvalidChar {
+            column: 9,
+            value: '}'
+        })
+    );
+    assert!("1 1".parse::<Value>().is_err());
+    assert!("--1".parse::<Value>().is_err());
+    assert!("\"\\ud800\"".parse::<Value>().is_err());
+}
@@ -1,46 +1,132 @@
-use std::fmt::Debug;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+pub mod animate;
+pub mod cli;
+pub mod collections;
+pub mod cycle;
+pub mod geom;
+pub mod graph;
+pub mod grid;
+pub mod image;
+pub mod input;
+pub mod json;
+pub mod math;
+pub mod ocr;
+pub mod overflow;
+pub mod parse;
+pub mod prefix;
+pub mod progress;
+pub mod ranges;
+pub mod record;
+pub mod rects;
+pub mod run;
+pub mod sim;
+pub mod solve;
+pub mod testing;
+pub mod trace;
+
+use std::error::Error;
+use std::fmt::{self, Debug};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
 use std::path::Path;
 use std::str::FromStr;
 
-pub fn buf_reader_from_arg() -> Result<BufReader<File>, ()> {
-    // TODO: Fix result, add error handling
-    let args: Vec<String> = std::env::args().collect();
-    let bin_path = Path::new(&args[0]);
+/// Error for command line arguments that can't be used as puzzle input. The
+/// usage text has already been printed when the number of arguments is wrong
+#[derive(Debug)]
+pub enum ArgError {
+    Count { expected: usize, found: usize },
+    Io { path: String, error: io::Error },
+    InvalidValue(String),
+}
 
-    if args.len() != 2 {
-        println!(
-            "Usage: {} <path>",
-            bin_path.file_name().unwrap().to_str().unwrap()
-        );
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgError::Count { expected, found } => {
+                write!(f, "expected {} arguments, got {}", expected, found)
+            }
+            ArgError::Io { path, error } => write!(f, "{}: {}", path, error),
+            ArgError::InvalidValue(value) => write!(f, "invalid argument {:?}", value),
+        }
+    }
+}
+
+impl Error for ArgError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ArgError::Io { error, .. } => Some(error),
+            _ => None,
+        }
     }
-    Ok(BufReader::new(File::open(&args[1]).unwrap()))
 }
 
-pub fn get_args<T: Debug + FromStr>(num_args: usize) -> Result<Vec<T>, ()> {
-    let args: Vec<String> = std::env::args().collect();
-    let bin_path = Path::new(&args[0]);
+/// The single positional argument, or the usage text and an error
+fn path_from_arg() -> Result<&'static str, ArgError> {
+    let args = cli::args();
+    if args.positional.len() != 1 {
+        println!("{}", args.usage("<path>"));
+        return Err(ArgError::Count {
+            expected: 1,
+            found: args.positional.len(),
+        });
+    }
+    Ok(&args.positional[0])
+}
 
-    if args.len() != num_args + 1 {
-        print!(
-            "Usage: {}",
-            bin_path.file_name().ok_or(())?.to_str().ok_or(())?
-        );
-        for i in 1..=num_args {
-            print!(" <arg-{}>", i);
+fn io_error(path: &str) -> impl FnOnce(io::Error) -> ArgError + '_ {
+    move |error| ArgError::Io {
+        path: path.to_owned(),
+        error,
+    }
+}
+
+pub fn buf_reader_from_arg() -> Result<BufReader<File>, ArgError> {
+    let path = path_from_arg()?;
+    File::open(path).map(BufReader::new).map_err(io_error(path))
+}
+
+pub fn string_from_arg() -> Result<String, ArgError> {
+    let path = path_from_arg()?;
+    fs::read_to_string(path).map_err(io_error(path))
+}
+
+/// Parse the given number of arguments. Instead of the arguments themselves,
+/// a path to the puzzle input may be given, in which case the integers in the
+/// puzzle text are used as arguments
+pub fn get_args<T: Debug + FromStr>(num_args: usize) -> Result<Vec<T>, ArgError> {
+    let args = cli::args();
+
+    let from_file = match args.positional.as_slice() {
+        [path] if Path::new(path).is_file() => {
+            let text = fs::read_to_string(path).map_err(io_error(path))?;
+            Some(parse::integer_tokens(&text).map(str::to_owned).collect())
         }
-        println!();
-        return Err(());
+        _ => None,
+    };
+    let values: Vec<String> = from_file.unwrap_or_else(|| args.positional.clone());
+
+    if values.len() != num_args {
+        let placeholders: Vec<String> = (1..=num_args).map(|i| format!("<arg-{}>", i)).collect();
+        println!(
+            "{}",
+            args.usage(&format!("<path>|{}", placeholders.join(" ")))
+        );
+        return Err(ArgError::Count {
+            expected: num_args,
+            found: values.len(),
+        });
     }
 
-    args.into_iter()
-        .skip(1)
-        .map(|x| x.parse::<T>().or(Err(())))
+    values
+        .iter()
+        .map(|x| {
+            x.parse::<T>()
+                .map_err(|_| ArgError::InvalidValue(x.clone()))
+        })
         .collect()
 }
 
-pub fn get_arg<T: Debug + FromStr>() -> Result<T, ()> {
+pub fn get_arg<T: Debug + FromStr>() -> Result<T, ArgError> {
     Ok(get_args(1)?.swap_remove(0))
 }
 
@@ -0,0 +1,237 @@
+use std::fmt::Debug;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+/// Signed integer type that the number theory functions work on
+pub trait Int:
+    Copy
+    + Debug
+    + Ord
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+    + Neg<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    /// `a * b mod m` for `0 <= a, b < m`, without overflowing
+    fn mul_mod(a: Self, b: Self, m: Self) -> Self;
+
+    fn rem_euclid(self, m: Self) -> Self;
+}
+
+impl Int for i64 {
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+
+    fn mul_mod(a: Self, b: Self, m: Self) -> Self {
+        (a as i128 * b as i128 % m as i128) as i64
+    }
+
+    fn rem_euclid(self, m: Self) -> Self {
+        i64::rem_euclid(self, m)
+    }
+}
+
+impl Int for i128 {
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+
+    fn mul_mod(mut a: Self, mut b: Self, m: Self) -> Self {
+        // There is no wider type to fall back on, so multiply by doubling and
+        // adding. Additions are written so that they can't overflow either
+        let add_mod = |x: i128, y: i128| if x >= m - y { x - (m - y) } else { x + y };
+        let mut product = 0;
+        while b > 0 {
+            if b & 1 == 1 {
+                product = add_mod(product, a);
+            }
+            a = add_mod(a, a);
+            b >>= 1;
+        }
+        product
+    }
+
+    fn rem_euclid(self, m: Self) -> Self {
+        i128::rem_euclid(self, m)
+    }
+}
+
+/// Greatest common divisor. The result is never negative, and `gcd(0, 0)` is 0
+pub fn gcd<T: Int>(a: T, b: T) -> T {
+    let (mut a, mut b) = (a, b);
+    while b != T::ZERO {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    if a < T::ZERO {
+        -a
+    } else {
+        a
+    }
+}
+
+/// Least common multiple. The result is never negative, and 0 if either
+/// number is 0
+pub fn lcm<T: Int>(a: T, b: T) -> T {
+    if a == T::ZERO || b == T::ZERO {
+        return T::ZERO;
+    }
+    let l = a / gcd(a, b) * b;
+    if l < T::ZERO {
+        -l
+    } else {
+        l
+    }
+}
+
+/// Quotient and remainder of `a / m` where the remainder is in `0..|m|`, so
+/// that `a = q * m + r`. Panics if `m` is 0
+pub fn div_rem_euclid<T: Int>(a: T, m: T) -> (T, T) {
+    let r = a.rem_euclid(m);
+    ((a - r) / m, r)
+}
+
+/// Returns `(g, x, y)` where `g = gcd(a, b)` and `a * x + b * y = g`
+pub fn extended_gcd<T: Int>(a: T, b: T) -> (T, T, T) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (T::ONE, T::ZERO);
+    let (mut old_y, mut y) = (T::ZERO, T::ONE);
+    while r != T::ZERO {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    if old_r < T::ZERO {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// `base` raised to `exp` modulo `m`. Panics if `m` isn't positive or `exp`
+/// is negative
+pub fn mod_pow<T: Int>(base: T, exp: T, m: T) -> T {
+    assert!(m > T::ZERO, "Modulus must be positive, got {:?}", m);
+    assert!(
+        exp >= T::ZERO,
+        "Exponent must not be negative, got {:?}",
+        exp
+    );
+
+    let two = T::ONE + T::ONE;
+    let mut base = base.rem_euclid(m);
+    let mut exp = exp;
+    let mut result = T::ONE.rem_euclid(m);
+    while exp > T::ZERO {
+        if exp % two == T::ONE {
+            result = T::mul_mod(result, base, m);
+        }
+        base = T::mul_mod(base, base, m);
+        exp = exp / two;
+    }
+    result
+}
+
+/// The `x` in `0..m` where `a * x = 1 (mod m)`, if `a` and `m` are coprime.
+/// Panics if `m` isn't positive
+pub fn mod_inv<T: Int>(a: T, m: T) -> Option<T> {
+    assert!(m > T::ZERO, "Modulus must be positive, got {:?}", m);
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+    if g == T::ONE {
+        Some(x.rem_euclid(m))
+    } else {
+        None
+    }
+}
+
+/// Solve the system `x = r (mod m)` for every `(r, m)` pair using the Chinese
+/// remainder theorem. The moduli don't have to be coprime. Returns the
+/// smallest non-negative solution along with the modulus that all solutions
+/// repeat at, or `None` if the congruences contradict each other. Panics if a
+/// modulus isn't positive
+pub fn crt<T: Int>(congruences: impl IntoIterator<Item = (T, T)>) -> Option<(T, T)> {
+    let mut x = T::ZERO;
+    let mut modulus = T::ONE;
+    for (r, m) in congruences {
+        assert!(m > T::ZERO, "Modulus must be positive, got {:?}", m);
+        let r = r.rem_euclid(m);
+
+        // Find k such that x + modulus * k = r (mod m)
+        let (g, inv, _) = extended_gcd(modulus, m);
+        let diff = (r - x).rem_euclid(m);
+        if diff % g != T::ZERO {
+            return None;
+        }
+        let step = m / g;
+        let k = T::mul_mod(diff / g % step, inv.rem_euclid(step), step);
+
+        // Since k < step, the new solution stays below the new modulus
+        x = x + modulus * k;
+        modulus = modulus * step;
+    }
+    Some((x, modulus))
+}
+
+#[test]
+fn test_gcd_lcm() {
+    assert_eq!(gcd(12i64, 18), 6);
+    assert_eq!(gcd(-12i64, 18), 6);
+    assert_eq!(gcd(0i64, -5), 5);
+    assert_eq!(gcd(0i64, 0), 0);
+    assert_eq!(lcm(4i64, -6), 12);
+    assert_eq!(lcm(0i64, 6), 0);
+    assert_eq!(lcm(1i128 << 100, 3), 3 << 100);
+
+    for &(a, b) in &[(240i64, 46), (-7, 3), (5, 0), (17, 17)] {
+        let (g, x, y) = extended_gcd(a, b);
+        assert_eq!(g, gcd(a, b));
+        assert_eq!(a * x + b * y, g);
+    }
+}
+
+#[test]
+fn test_div_rem_euclid() {
+    assert_eq!(div_rem_euclid(7i64, 3), (2, 1));
+    assert_eq!(div_rem_euclid(-7i64, 3), (-3, 2));
+    assert_eq!(div_rem_euclid(7i64, -3), (-2, 1));
+    assert_eq!(div_rem_euclid(-7i64, -3), (3, 2));
+    assert_eq!(div_rem_euclid(i128::MIN, 2), (i128::MIN / 2, 0));
+}
+
+#[test]
+fn test_modular() {
+    assert_eq!(mod_pow(4i64, 13, 497), 445);
+    assert_eq!(mod_pow(-2i64, 3, 5), 2);
+    assert_eq!(mod_pow(7i64, 0, 1), 0);
+    assert_eq!(mod_pow(i64::MAX - 1, i64::MAX, i64::MAX), i64::MAX - 1);
+
+    // 2^127 - 1 is prime, so by Fermat's little theorem a^(p - 1) = 1
+    let p = i128::MAX;
+    assert_eq!(mod_pow(3i128, p - 1, p), 1);
+
+    assert_eq!(mod_inv(3i64, 11), Some(4));
+    assert_eq!(mod_inv(-3i64, 11), Some(7));
+    assert_eq!(mod_inv(6i64, 9), None);
+    assert_eq!(mod_inv(2i128, p), Some(p / 2 + 1));
+}
+
+#[test]
+fn test_crt() {
+    assert_eq!(crt(vec![(2i64, 3), (3, 5), (2, 7)]), Some((23, 105)));
+    assert_eq!(crt(vec![(-1i64, 4), (3, 6)]), Some((3, 12)));
+    assert_eq!(crt(vec![(1i64, 4), (2, 6)]), None);
+    assert_eq!(crt(Vec::<(i64, i64)>::new()), Some((0, 1)));
+
+    // Moduli whose product doesn't fit in an i64
+    let (x, m) = crt(vec![(1i128, 1_000_000_007), (2, 998_244_353), (3, 1 << 40)]).unwrap();
+    assert_eq!(m, 1_000_000_007 * 998_244_353 * (1 << 40));
+    assert_eq!(
+        (x % 1_000_000_007, x % 998_244_353, x % (1 << 40)),
+        (1, 2, 3)
+    );
+}
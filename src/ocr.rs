@@ -0,0 +1,307 @@
+use crate::geom::Point2;
+use crate::grid::Grid;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// Letters of the font that is six pixels high. Glyphs have no empty columns
+/// on either side
+const SMALL_FONT: [(char, &str); 18] = [
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', "###\n.#.\n.#.\n.#.\n.#.\n###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...#\n#...#\n.#.#.\n..#..\n..#..\n..#.."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+/// Letters of the font that is ten pixels high
+const LARGE_FONT: [(char, &str); 15] = [
+    (
+        'A',
+        "..##..\n.#..#.\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'B',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#....#\n#....#\n#....#\n#....#\n#####.",
+    ),
+    (
+        'C',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#....#\n.####.",
+    ),
+    (
+        'E',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'F',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'G',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#..###\n#....#\n#....#\n#...##\n.###.#",
+    ),
+    (
+        'H',
+        "#....#\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'J',
+        "...###\n....#.\n....#.\n....#.\n....#.\n....#.\n....#.\n#...#.\n#...#.\n.###..",
+    ),
+    (
+        'K',
+        "#....#\n#...#.\n#..#..\n#.#...\n##....\n##....\n#.#...\n#..#..\n#...#.\n#....#",
+    ),
+    (
+        'L',
+        "#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'N',
+        "#....#\n##...#\n##...#\n#.#..#\n#.#..#\n#..#.#\n#..#.#\n#...##\n#...##\n#....#",
+    ),
+    (
+        'P',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'R',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#..#..\n#...#.\n#...#.\n#....#\n#....#",
+    ),
+    (
+        'X',
+        "#....#\n#....#\n.#..#.\n.#..#.\n..##..\n..##..\n.#..#.\n.#..#.\n#....#\n#....#",
+    ),
+    (
+        'Z',
+        "######\n.....#\n.....#\n....#.\n...#..\n..#...\n.#....\n#.....\n#.....\n######",
+    ),
+];
+
+lazy_static! {
+    /// Glyph lookup by the height of the font
+    static ref fonts: HashMap<usize, HashMap<&'static str, char>> = {
+        let mut by_height = HashMap::new();
+        by_height.insert(6, SMALL_FONT.iter().map(|&(c, g)| (g, c)).collect());
+        by_height.insert(10, LARGE_FONT.iter().map(|&(c, g)| (g, c)).collect());
+        by_height
+    };
+}
+
+/// Glyph that isn't in the font
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnknownGlyph {
+    /// Index of the glyph in the decoded text
+    pub index: usize,
+    /// Rendered as `#` and `.`, one line per row
+    pub bitmap: String,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum OcrError {
+    /// No pixels are set
+    Empty,
+    /// There is no font with this many rows of pixels
+    UnsupportedHeight(usize),
+    /// Some glyphs weren't recognized. The text has `?` in their place
+    UnknownGlyphs {
+        text: String,
+        glyphs: Vec<UnknownGlyph>,
+    },
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OcrError::Empty => write!(f, "no pixels to decode"),
+            OcrError::UnsupportedHeight(height) => {
+                write!(f, "no font is {} pixels high, expected 6 or 10", height)
+            }
+            OcrError::UnknownGlyphs { text, glyphs } => {
+                write!(f, "unknown glyphs in {:?}", text)?;
+                for glyph in glyphs {
+                    write!(f, "\nglyph {}:\n{}", glyph.index, glyph.bitmap)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for OcrError {}
+
+/// Decode the letters in a bitmap where `true` is a lit pixel. Empty rows and
+/// columns around the text are ignored, and letters must be separated by at
+/// least one empty column
+pub fn decode(grid: &Grid<bool>) -> Result<String, OcrError> {
+    let lit_rows: Vec<usize> = (0..grid.height())
+        .filter(|&y| grid.row(y).iter().any(|&p| p))
+        .collect();
+    let (top, bottom) = match (lit_rows.first(), lit_rows.last()) {
+        (Some(&top), Some(&bottom)) => (top, bottom),
+        _ => return Err(OcrError::Empty),
+    };
+    let height = bottom - top + 1;
+    let font = fonts
+        .get(&height)
+        .ok_or(OcrError::UnsupportedHeight(height))?;
+
+    // Consecutive runs of columns with at least one lit pixel
+    let mut spans = Vec::new();
+    let mut start = None;
+    for x in 0..=grid.width() {
+        let lit = x < grid.width() && grid.column(x).any(|&p| p);
+        match (lit, start) {
+            (true, None) => start = Some(x),
+            (false, Some(s)) => {
+                spans.push(s..x);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    let mut text = String::with_capacity(spans.len());
+    let mut unknown = Vec::new();
+    for (index, span) in spans.into_iter().enumerate() {
+        let bitmap = (top..=bottom)
+            .map(|y| {
+                span.clone()
+                    .map(|x| if grid[(x, y)] { '#' } else { '.' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        match font.get(bitmap.as_str()) {
+            Some(&c) => text.push(c),
+            None => {
+                text.push('?');
+                unknown.push(UnknownGlyph { index, bitmap });
+            }
+        }
+    }
+
+    if unknown.is_empty() {
+        Ok(text)
+    } else {
+        Err(OcrError::UnknownGlyphs {
+            text,
+            glyphs: unknown,
+        })
+    }
+}
+
+/// Decode the letters drawn by a set of lit points, where `y` grows downwards
+pub fn decode_points(points: impl IntoIterator<Item = Point2>) -> Result<String, OcrError> {
+    decode(&Grid::from_points(points).1)
+}
+
+#[cfg(test)]
+fn bitmap(s: &str) -> Grid<bool> {
+    Grid::parse_with(s, |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })
+    .unwrap()
+}
+
+/// Draw the glyphs next to each other with the given spacing
+#[cfg(test)]
+fn render(font: &[(char, &str)], text: &str, spacing: usize) -> String {
+    let glyphs: Vec<Vec<&str>> = text
+        .chars()
+        .map(|c| font.iter().find(|g| g.0 == c).unwrap().1.lines().collect())
+        .collect();
+    let gap = ".".repeat(spacing);
+    (0..glyphs[0].len())
+        .map(|y| {
+            let row: Vec<&str> = glyphs.iter().map(|g| g[y]).collect();
+            format!("{}{}{}", gap, row.join(&gap), gap)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn test_fonts() {
+    let small: String = SMALL_FONT.iter().map(|g| g.0).collect();
+    assert_eq!(decode(&bitmap(&render(&SMALL_FONT, &small, 1))), Ok(small));
+
+    let large: String = LARGE_FONT.iter().map(|g| g.0).collect();
+    assert_eq!(decode(&bitmap(&render(&LARGE_FONT, &large, 2))), Ok(large));
+}
+
+#[test]
+fn test_decode_text() {
+    // Letters are drawn in cells five pixels wide, so glyphs narrower than
+    // four pixels have more space around them
+    let screen = concat!(
+        "#..#..###...##..##...##..#..#.\n",
+        "#..#...#.....#.#..#.#..#.#.#..\n",
+        "####...#.....#.#..#.#....##...\n",
+        "#..#...#.....#.####.#....#.#..\n",
+        "#..#...#..#..#.#..#.#..#.#.#..\n",
+        "#..#..###..##..#..#..##..#..#.\n",
+    );
+    assert_eq!(decode(&bitmap(screen)), Ok("HIJACK".to_owned()));
+}
+
+#[test]
+fn test_decode_points() {
+    // Points don't have to start at the origin
+    let grid = bitmap(&render(&LARGE_FONT, "HE", 2));
+    let points = grid
+        .enumerate()
+        .filter(|(_, &lit)| lit)
+        .map(|((x, y), _)| Point2::new(x as i64 - 50, y as i64 - 20));
+    assert_eq!(decode_points(points), Ok("HE".to_owned()));
+    assert_eq!(decode_points(Vec::new()), Err(OcrError::Empty));
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(
+        decode(&bitmap("....\n.##.\n....")),
+        Err(OcrError::UnsupportedHeight(1))
+    );
+
+    let grid = bitmap(&format!(
+        "{}\n{}",
+        render(&SMALL_FONT, "HEY", 1),
+        ".....#...........\n.....#..........."
+    ));
+    assert_eq!(decode(&grid), Err(OcrError::UnsupportedHeight(8)));
+
+    // The middle glyph is an E with a missing pixel
+    let mut grid = bitmap(&render(&SMALL_FONT, "HEY", 1));
+    grid[(7, 2)] = false;
+    let err = decode(&grid).unwrap_err();
+    assert_eq!(
+        err,
+        OcrError::UnknownGlyphs {
+            text: "H?Y".to_owned(),
+            glyphs: vec![UnknownGlyph {
+                index: 1,
+                bitmap: "####\n#...\n#.#.\n#...\n#...\n####".to_owned(),
+            }],
+        }
+    );
+    assert!(err
+        .to_string()
+        .starts_with("unknown glyphs in \"H?Y\"\nglyph 1:\n"));
+}
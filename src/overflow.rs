@@ -0,0 +1,232 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// How arithmetic that doesn't fit in its type is handled. Using this instead
+/// of the plain operators makes the behaviour the same in debug and release
+/// builds
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Overflow {
+    /// Overflow is reported as an error
+    #[default]
+    Checked,
+    /// Results wrap around at the boundary of the type
+    Wrapping,
+    /// Results are clamped to the smallest or largest value of the type
+    Saturating,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OverflowError {
+    pub op: &'static str,
+    pub lhs: String,
+    pub rhs: String,
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {} overflowed", self.lhs, self.op, self.rhs)
+    }
+}
+
+impl Error for OverflowError {}
+
+/// Primitive integer that supports all overflow policies
+pub trait Integer: Copy + fmt::Display {
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn wrapping_add(self, other: Self) -> Self;
+    fn wrapping_sub(self, other: Self) -> Self;
+    fn wrapping_mul(self, other: Self) -> Self;
+    fn saturating_add(self, other: Self) -> Self;
+    fn saturating_sub(self, other: Self) -> Self;
+    fn saturating_mul(self, other: Self) -> Self;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Integer for $t {
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$t>::checked_add(self, other)
+                }
+
+                fn checked_sub(self, other: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, other)
+                }
+
+                fn checked_mul(self, other: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, other)
+                }
+
+                fn wrapping_add(self, other: Self) -> Self {
+                    <$t>::wrapping_add(self, other)
+                }
+
+                fn wrapping_sub(self, other: Self) -> Self {
+                    <$t>::wrapping_sub(self, other)
+                }
+
+                fn wrapping_mul(self, other: Self) -> Self {
+                    <$t>::wrapping_mul(self, other)
+                }
+
+                fn saturating_add(self, other: Self) -> Self {
+                    <$t>::saturating_add(self, other)
+                }
+
+                fn saturating_sub(self, other: Self) -> Self {
+                    <$t>::saturating_sub(self, other)
+                }
+
+                fn saturating_mul(self, other: Self) -> Self {
+                    <$t>::saturating_mul(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl Overflow {
+    fn apply<T: Integer>(
+        self,
+        op: &'static str,
+        lhs: T,
+        rhs: T,
+        checked: fn(T, T) -> Option<T>,
+        wrapping: fn(T, T) -> T,
+        saturating: fn(T, T) -> T,
+    ) -> Result<T, OverflowError> {
+        match self {
+            Overflow::Checked => checked(lhs, rhs).ok_or_else(|| OverflowError {
+                op,
+                lhs: lhs.to_string(),
+                rhs: rhs.to_string(),
+            }),
+            Overflow::Wrapping => Ok(wrapping(lhs, rhs)),
+            Overflow::Saturating => Ok(saturating(lhs, rhs)),
+        }
+    }
+
+    pub fn add<T: Integer>(self, lhs: T, rhs: T) -> Result<T, OverflowError> {
+        self.apply(
+            "+",
+            lhs,
+            rhs,
+            T::checked_add,
+            T::wrapping_add,
+            T::saturating_add,
+        )
+    }
+
+    pub fn sub<T: Integer>(self, lhs: T, rhs: T) -> Result<T, OverflowError> {
+        self.apply(
+            "-",
+            lhs,
+            rhs,
+            T::checked_sub,
+            T::wrapping_sub,
+            T::saturating_sub,
+        )
+    }
+
+    pub fn mul<T: Integer>(self, lhs: T, rhs: T) -> Result<T, OverflowError> {
+        self.apply(
+            "*",
+            lhs,
+            rhs,
+            T::checked_mul,
+            T::wrapping_mul,
+            T::saturating_mul,
+        )
+    }
+
+    /// Add up all values, starting from zero
+    pub fn sum<T: Integer + Default>(
+        self,
+        values: impl IntoIterator<Item = T>,
+    ) -> Result<T, OverflowError> {
+        values
+            .into_iter()
+            .try_fold(T::default(), |sum, value| self.add(sum, value))
+    }
+}
+
+/// Convert an integer to another integer type. Values that don't fit are an
+/// error no matter the policy, since there is no sensible way to wrap them
+pub fn convert<T, U>(value: T) -> Result<U, OverflowError>
+where
+    T: Copy + fmt::Display,
+    U: TryFrom<T>,
+{
+    U::try_from(value).map_err(|_| OverflowError {
+        op: "as",
+        lhs: value.to_string(),
+        rhs: std::any::type_name::<U>().to_owned(),
+    })
+}
+
+impl FromStr for Overflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "checked" => Ok(Overflow::Checked),
+            "wrapping" => Ok(Overflow::Wrapping),
+            "saturating" => Ok(Overflow::Saturating),
+            _ => Err(format!(
+                "Unknown overflow policy {:?}, expected checked, wrapping or saturating",
+                s
+            )),
+        }
+    }
+}
+
+#[test]
+fn test_policies() {
+    assert_eq!(Overflow::Checked.add(250u8, 5), Ok(255));
+    assert_eq!(
+        Overflow::Checked.add(250u8, 6),
+        Err(OverflowError {
+            op: "+",
+            lhs: "250".to_owned(),
+            rhs: "6".to_owned()
+        })
+    );
+    assert_eq!(Overflow::Wrapping.add(250u8, 6), Ok(0));
+    assert_eq!(Overflow::Saturating.add(250u8, 6), Ok(255));
+
+    assert_eq!(Overflow::Wrapping.sub(0usize, 1), Ok(usize::MAX));
+    assert_eq!(Overflow::Saturating.sub(0usize, 1), Ok(0));
+    assert_eq!(Overflow::Saturating.mul(-2i64, i64::MAX), Ok(i64::MIN));
+    assert!(Overflow::Checked.mul(i64::MAX, 2).is_err());
+
+    assert_eq!(Overflow::Checked.sum(vec![100i8, 27]), Ok(127));
+    assert!(Overflow::Checked.sum(vec![100i8, 27, 1]).is_err());
+    assert_eq!(Overflow::Saturating.sum(vec![100i8, 27, 1, -10]), Ok(117));
+
+    assert_eq!(convert::<usize, i8>(127), Ok(127i8));
+    assert_eq!(
+        convert::<usize, i8>(128).map_err(|e| e.to_string()),
+        Err("128 as i8 overflowed".to_owned())
+    );
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!("wrapping".parse(), Ok(Overflow::Wrapping));
+    assert!("wrap".parse::<Overflow>().is_err());
+    assert_eq!(
+        OverflowError {
+            op: "*",
+            lhs: "3".to_owned(),
+            rhs: "4".to_owned()
+        }
+        .to_string(),
+        "3 * 4 overflowed"
+    );
+}
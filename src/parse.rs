@@ -0,0 +1,416 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Match, Regex};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseError {
+    /// The input did not match the expected pattern
+    NoMatch(String),
+    /// A capture group that is required did not participate in the match
+    MissingGroup(usize),
+    /// A capture group could not be converted to the expected type
+    InvalidValue { group: usize, value: String },
+    /// A character that has no meaning at this position
+    InvalidChar { column: usize, value: char },
+    /// The input ended before everything expected was found
+    UnexpectedEnd,
+    /// The input has a different number of sections separated by blank lines
+    SectionCount { expected: usize, found: usize },
+    /// Error that happened in the block or line starting at the given line
+    AtLine { line: usize, error: Box<ParseError> },
+    /// A field that is required is not in the record, or is null
+    MissingField(String),
+    /// A field could not be converted to the expected type
+    InvalidField { field: String, value: String },
+    /// Error in the given record of a JSON array, counting from one
+    AtRecord {
+        index: usize,
+        error: Box<ParseError>,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::NoMatch(input) => write!(f, "unexpected input {:?}", input),
+            ParseError::MissingGroup(group) => write!(f, "capture group {} is missing", group),
+            ParseError::InvalidValue { group, value } => {
+                write!(f, "invalid value {:?} in capture group {}", value, group)
+            }
+            ParseError::InvalidChar { column, value } => {
+                write!(f, "invalid character {:?} in column {}", value, column)
+            }
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            ParseError::SectionCount { expected, found } => write!(
+                f,
+                "expected {} sections separated by blank lines, found {}",
+                expected, found
+            ),
+            ParseError::AtLine { line, error } => write!(f, "line {}: {}", line, error),
+            ParseError::MissingField(field) => write!(f, "field {:?} is missing", field),
+            ParseError::InvalidField { field, value } => {
+                write!(f, "invalid value {:?} in field {:?}", value, field)
+            }
+            ParseError::AtRecord { index, error } => write!(f, "record {}: {}", index, error),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Conversion of a single regex capture group into a typed value
+pub trait FromCapture: Sized {
+    fn from_capture(group: usize, capture: Option<Match>) -> Result<Self, ParseError>;
+}
+
+/// Capture conversion for any type that implements `FromStr`
+pub fn capture_from_str<T: FromStr>(group: usize, capture: Option<Match>) -> Result<T, ParseError> {
+    let value = capture.ok_or(ParseError::MissingGroup(group))?.as_str();
+    value.parse::<T>().map_err(|_| ParseError::InvalidValue {
+        group,
+        value: value.to_owned(),
+    })
+}
+
+/// Parse the whole text, apart from surrounding whitespace, as one value
+pub fn parse_value<T: FromStr>(s: &str) -> Result<T, ParseError> {
+    s.trim()
+        .parse()
+        .map_err(|_| ParseError::NoMatch(s.to_owned()))
+}
+
+macro_rules! impl_from_capture {
+    ($($t:ty),*) => {
+        $(
+            impl FromCapture for $t {
+                fn from_capture(group: usize, capture: Option<Match>) -> Result<Self, ParseError> {
+                    capture_from_str(group, capture)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_capture!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f64, bool, char, String
+);
+
+/// Optional capture groups become `None` when they did not participate
+impl<T: FromCapture> FromCapture for Option<T> {
+    fn from_capture(group: usize, capture: Option<Match>) -> Result<Self, ParseError> {
+        match capture {
+            Some(_) => T::from_capture(group, capture).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Conversion of all capture groups of a match into a tuple. The first
+/// element of the tuple is capture group 1
+pub trait FromCaptures: Sized {
+    fn from_captures(captures: &Captures) -> Result<Self, ParseError>;
+}
+
+macro_rules! impl_from_captures {
+    ($($t:ident => $group:expr),*) => {
+        impl<$($t: FromCapture),*> FromCaptures for ($($t,)*) {
+            fn from_captures(captures: &Captures) -> Result<Self, ParseError> {
+                Ok(($($t::from_capture($group, captures.get($group))?,)*))
+            }
+        }
+    };
+}
+
+impl_from_captures!(A => 1);
+impl_from_captures!(A => 1, B => 2);
+impl_from_captures!(A => 1, B => 2, C => 3);
+impl_from_captures!(A => 1, B => 2, C => 3, D => 4);
+impl_from_captures!(A => 1, B => 2, C => 3, D => 4, E => 5);
+impl_from_captures!(A => 1, B => 2, C => 3, D => 4, E => 5, F => 6);
+impl_from_captures!(A => 1, B => 2, C => 3, D => 4, E => 5, F => 6, G => 7);
+impl_from_captures!(A => 1, B => 2, C => 3, D => 4, E => 5, F => 6, G => 7, H => 8);
+
+/// Match the whole pattern against the input and convert the capture groups
+pub fn parse_captures<T: FromCaptures>(re: &Regex, s: &str) -> Result<T, ParseError> {
+    let captures = re
+        .captures(s)
+        .ok_or_else(|| ParseError::NoMatch(s.to_owned()))?;
+    T::from_captures(&captures)
+}
+
+/// All integers in a piece of text, like the numbers in "10 players; last
+/// marble is worth 1618 points". Leading zeros are kept so the tokens can be
+/// used as digit sequences as well
+pub fn integer_tokens(s: &str) -> impl Iterator<Item = &str> {
+    lazy_static! {
+        static ref int_re: Regex = Regex::new(r"-?\d+").unwrap();
+    }
+    int_re.find_iter(s).map(|m| m.as_str())
+}
+
+/// A chunk of input that knows which line it starts at, so errors can point
+/// out where in the input they happened
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Block<'a> {
+    pub line: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Block<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self { line: 1, text }
+    }
+
+    /// Sub-blocks separated by one or more blank lines
+    pub fn blocks(&self) -> Vec<Block<'a>> {
+        self.sections(1)
+    }
+
+    /// Sub-blocks separated by at least the given number of blank lines.
+    /// Shorter runs of blank lines are kept within the sections
+    pub fn sections(&self, min_blank_lines: usize) -> Vec<Block<'a>> {
+        let mut sections = Vec::new();
+        let mut start = None;
+        let mut end = 0;
+        let mut num_blank = 0;
+        let mut offset = 0;
+
+        for (i, line) in self.text.split_inclusive('\n').enumerate() {
+            if line.trim().is_empty() {
+                num_blank += 1;
+            } else {
+                if num_blank >= min_blank_lines {
+                    if let Some((line, start)) = start.take() {
+                        sections.push(self.slice(line, start, end));
+                    }
+                }
+                if start.is_none() {
+                    start = Some((self.line + i, offset));
+                }
+                num_blank = 0;
+                end = offset + line.len();
+            }
+            offset += line.len();
+        }
+
+        if let Some((line, start)) = start {
+            sections.push(self.slice(line, start, end));
+        }
+        sections
+    }
+
+    /// Split into a header and a body at the first blank line
+    pub fn header_body(&self) -> Result<(Block<'a>, Block<'a>), ParseError> {
+        let mut blocks = self.blocks().into_iter();
+        let header = blocks.next().ok_or(ParseError::UnexpectedEnd)?;
+        let body_start = blocks.next().ok_or_else(|| header.error_after())?;
+
+        let offset = body_start.text.as_ptr() as usize - self.text.as_ptr() as usize;
+        let body = self.slice(body_start.line, offset, self.text.trim_end().len());
+        Ok((header, body))
+    }
+
+    /// Non-blank lines of the block along with their line numbers
+    pub fn lines(&self) -> impl Iterator<Item = Block<'a>> {
+        let first_line = self.line;
+        self.text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(move |(i, line)| Block {
+                line: first_line + i,
+                text: line,
+            })
+    }
+
+    pub fn parse<T>(&self) -> Result<T, ParseError>
+    where
+        T: FromStr<Err = ParseError>,
+    {
+        self.text.parse::<T>().map_err(|error| self.error(error))
+    }
+
+    /// Parse each sub-block separated by blank lines
+    pub fn parse_blocks<T>(&self) -> Result<Vec<T>, ParseError>
+    where
+        T: FromStr<Err = ParseError>,
+    {
+        self.blocks().iter().map(Block::parse).collect()
+    }
+
+    /// Parse each non-blank line
+    pub fn parse_lines<T>(&self) -> Result<Vec<T>, ParseError>
+    where
+        T: FromStr<Err = ParseError>,
+    {
+        self.lines().map(|line| line.parse()).collect()
+    }
+
+    /// Wrap an error with the line this block starts at
+    pub fn error(&self, error: ParseError) -> ParseError {
+        match error {
+            ParseError::AtLine { .. } => error,
+            error => ParseError::AtLine {
+                line: self.line,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    fn error_after(&self) -> ParseError {
+        ParseError::AtLine {
+            line: self.line + self.text.lines().count(),
+            error: Box::new(ParseError::UnexpectedEnd),
+        }
+    }
+
+    fn slice(&self, line: usize, start: usize, end: usize) -> Block<'a> {
+        Block {
+            line,
+            text: self.text[start..end].trim_end_matches(['\r', '\n']),
+        }
+    }
+}
+
+#[test]
+fn test_parse_captures() {
+    let re = Regex::new(r"^#(\d+) @ (\w)(?: (x))?$").unwrap();
+
+    assert_eq!(
+        parse_captures::<(usize, char, Option<String>)>(&re, "#12 @ a"),
+        Ok((12, 'a', None))
+    );
+    assert_eq!(
+        parse_captures::<(u8, char, Option<String>)>(&re, "#12 @ a x"),
+        Ok((12, 'a', Some("x".to_owned())))
+    );
+    assert_eq!(
+        parse_captures::<(u8, char, String)>(&re, "#12 @ a"),
+        Err(ParseError::MissingGroup(3))
+    );
+    assert_eq!(
+        parse_captures::<(u8,)>(&re, "#1000 @ a"),
+        Err(ParseError::InvalidValue {
+            group: 1,
+            value: "1000".to_owned()
+        })
+    );
+    assert_eq!(
+        parse_captures::<(u8,)>(&re, "#1 @ "),
+        Err(ParseError::NoMatch("#1 @ ".to_owned()))
+    );
+}
+
+#[test]
+fn test_parse_value() {
+    assert_eq!(parse_value(" -12 "), Ok(-12i32));
+    assert_eq!(
+        parse_value::<u8>("12a"),
+        Err(ParseError::NoMatch("12a".to_owned()))
+    );
+}
+
+#[test]
+fn test_integer_tokens() {
+    assert_eq!(
+        integer_tokens("411 players; last marble is worth 71170 points").collect::<Vec<_>>(),
+        vec!["411", "71170"]
+    );
+    assert_eq!(
+        integer_tokens("x=-5, y=007\n").collect::<Vec<_>>(),
+        vec!["-5", "007"]
+    );
+}
+
+#[test]
+fn test_block_sections() {
+    let input = "a\nb\n\nc\n\n\n\nd\n\ne\n";
+    let block = Block::new(input);
+
+    assert_eq!(
+        block.blocks(),
+        vec![
+            Block {
+                line: 1,
+                text: "a\nb"
+            },
+            Block { line: 4, text: "c" },
+            Block { line: 8, text: "d" },
+            Block {
+                line: 10,
+                text: "e"
+            },
+        ]
+    );
+    assert_eq!(
+        block.sections(2),
+        vec![
+            Block {
+                line: 1,
+                text: "a\nb\n\nc"
+            },
+            Block {
+                line: 8,
+                text: "d\n\ne"
+            },
+        ]
+    );
+    assert_eq!(
+        block.sections(2)[1].blocks()[1],
+        Block {
+            line: 10,
+            text: "e"
+        }
+    );
+    assert_eq!(
+        block.header_body(),
+        Ok((
+            Block {
+                line: 1,
+                text: "a\nb"
+            },
+            Block {
+                line: 4,
+                text: "c\n\n\n\nd\n\ne"
+            },
+        ))
+    );
+    assert_eq!(
+        Block::new("a\nb\n").header_body(),
+        Err(ParseError::AtLine {
+            line: 3,
+            error: Box::new(ParseError::UnexpectedEnd)
+        })
+    );
+}
+
+#[test]
+fn test_block_parse() {
+    let block = Block::new("1\n2\n\nx\n");
+    assert_eq!(
+        block.parse_lines::<Number>(),
+        Err(ParseError::AtLine {
+            line: 4,
+            error: Box::new(ParseError::NoMatch("x".to_owned()))
+        })
+    );
+    assert_eq!(
+        block.blocks()[0].parse_lines::<Number>(),
+        Ok(vec![Number(1), Number(2)])
+    );
+
+    #[derive(Debug, PartialEq)]
+    struct Number(i32);
+
+    impl FromStr for Number {
+        type Err = ParseError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            s.parse()
+                .map(Number)
+                .map_err(|_| ParseError::NoMatch(s.to_owned()))
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::grid::Grid;
+use std::ops::{Add, Sub};
+
+/// Summed-area table (2D prefix sums) that gives the sum of any rectangle of
+/// values in constant time
+#[derive(Clone, Debug)]
+pub struct SummedArea<T> {
+    width: usize,
+    height: usize,
+    // Sum of all values above and to the left of each position. There is an
+    // extra row and column of zeros to avoid special cases at the edges
+    sums: Vec<T>,
+}
+
+impl<T> SummedArea<T>
+where
+    T: Copy + Default + Add<Output = T> + Sub<Output = T>,
+{
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(usize, usize) -> T) -> Self {
+        let stride = width + 1;
+        let mut sums = vec![T::default(); stride * (height + 1)];
+        for y in 0..height {
+            let mut row_sum = T::default();
+            for x in 0..width {
+                row_sum = row_sum + f(x, y);
+                sums[(x + 1) + (y + 1) * stride] = sums[(x + 1) + y * stride] + row_sum;
+            }
+        }
+        Self {
+            width,
+            height,
+            sums,
+        }
+    }
+
+    pub fn from_grid(grid: &Grid<T>) -> Self {
+        Self::from_fn(grid.width(), grid.height(), |x, y| grid[(x, y)])
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Sum of the `width` by `height` values with `(x, y)` as the top left
+    /// corner. Panics if the rectangle is not within bounds
+    pub fn sum(&self, x: usize, y: usize, width: usize, height: usize) -> T {
+        assert!(
+            x + width <= self.width && y + height <= self.height,
+            "Rectangle {}x{} at ({}, {}) is outside of {}x{} table",
+            width,
+            height,
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        let at = |x: usize, y: usize| self.sums[x + y * (self.width + 1)];
+        at(x + width, y + height) + at(x, y) - at(x + width, y) - at(x, y + height)
+    }
+
+    /// Sum of all values
+    pub fn total(&self) -> T {
+        self.sum(0, 0, self.width, self.height)
+    }
+
+    /// All `size` by `size` squares that fit, as `(sum, x, y)` where `(x, y)`
+    /// is the top left corner
+    pub fn squares(&self, size: usize) -> impl Iterator<Item = (T, usize, usize)> + '_ {
+        let xs = (self.width + 1).saturating_sub(size);
+        let ys = (self.height + 1).saturating_sub(size);
+        (0..xs * ys).map(move |i| {
+            let (x, y) = (i % xs, i / xs);
+            (self.sum(x, y, size, size), x, y)
+        })
+    }
+
+    /// Square of the given size with the largest sum as `(sum, x, y)`. Ties
+    /// are resolved in favor of the first square in row major order
+    pub fn best_square(&self, size: usize) -> Option<(T, usize, usize)>
+    where
+        T: Ord,
+    {
+        self.squares(size).fold(None, |best, candidate| match best {
+            Some(best) if best.0 >= candidate.0 => Some(best),
+            _ => Some(candidate),
+        })
+    }
+
+    /// Square of any of the given sizes with the largest sum as
+    /// `(sum, x, y, size)`. Ties are resolved in favor of the smallest size
+    pub fn best_square_of_sizes(
+        &self,
+        sizes: impl IntoIterator<Item = usize>,
+    ) -> Option<(T, usize, usize, usize)>
+    where
+        T: Ord,
+    {
+        sizes
+            .into_iter()
+            .filter_map(|size| {
+                let (sum, x, y) = self.best_square(size)?;
+                Some((sum, x, y, size))
+            })
+            .fold(None, |best, candidate| match best {
+                Some(best) if best.0 >= candidate.0 => Some(best),
+                _ => Some(candidate),
+            })
+    }
+}
+
+#[test]
+fn test_sum() {
+    let table = SummedArea::from_fn(4, 3, |x, y| (x + 10 * y) as i64);
+    assert_eq!(table.total(), 6 * 3 + 30 * 4);
+    assert_eq!(table.sum(1, 1, 2, 2), 11 + 12 + 21 + 22);
+    assert_eq!(table.sum(3, 2, 1, 1), 23);
+    assert_eq!(table.sum(2, 0, 0, 3), 0);
+
+    let grid = Grid::from_fn(4, 3, |x, y| (x + 10 * y) as i64);
+    assert_eq!(
+        SummedArea::from_grid(&grid).sum(0, 1, 4, 2),
+        table.sum(0, 1, 4, 2)
+    );
+}
+
+#[test]
+fn test_best_square() {
+    let grid: Grid<i32> = Grid::parse_with("1.9\n.1.\n9.1\n", |c| match c {
+        '.' => Some(-1),
+        c => c.to_digit(10).map(|d| d as i32),
+    })
+    .unwrap();
+    let table = SummedArea::from_grid(&grid);
+
+    assert_eq!(table.squares(2).count(), 4);
+    assert_eq!(table.best_square(1), Some((9, 2, 0)));
+    assert_eq!(table.best_square(2), Some((8, 1, 0)));
+    assert_eq!(table.best_square(4), None);
+    assert_eq!(table.best_square_of_sizes(1..=3), Some((17, 0, 0, 3)));
+}
+
+#[test]
+#[should_panic]
+fn test_sum_out_of_bounds() {
+    let table = SummedArea::from_fn(2, 2, |_, _| 1);
+    table.sum(1, 1, 2, 1);
+}
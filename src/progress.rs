@@ -0,0 +1,249 @@
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// Snapshot of how far a long running computation has come
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Report {
+    pub done: u64,
+    pub total: Option<u64>,
+    pub elapsed: Duration,
+}
+
+impl Report {
+    /// Number of items processed per second so far
+    pub fn rate(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.done as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Estimated time left. This is only available when the total is known
+    pub fn eta(&self) -> Option<Duration> {
+        let total = self.total?;
+        let rate = self.rate();
+        if rate <= 0.0 {
+            return None;
+        }
+        Some(Duration::from_secs_f64(
+            total.saturating_sub(self.done) as f64 / rate,
+        ))
+    }
+}
+
+/// Receiver of progress reports
+pub trait ProgressSink {
+    fn report(&mut self, report: &Report);
+
+    fn finish(&mut self, _report: &Report) {}
+}
+
+impl<F: FnMut(&Report)> ProgressSink for F {
+    fn report(&mut self, report: &Report) {
+        self(report)
+    }
+}
+
+/// Renders progress as a single line on stderr that is redrawn in place
+pub struct StderrLine {
+    label: String,
+}
+
+impl StderrLine {
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.to_owned(),
+        }
+    }
+
+    fn draw(&self, report: &Report) {
+        let mut line = format!("{}: {}", self.label, report.done);
+        if let Some(total) = report.total {
+            let pct = 100.0 * report.done as f64 / total.max(1) as f64;
+            line.push_str(&format!("/{} ({:.1}%)", total, pct));
+        }
+        line.push_str(&format!(", {:.0}/s", report.rate()));
+        if let Some(eta) = report.eta() {
+            line.push_str(&format!(", ETA {}s", eta.as_secs()));
+        }
+
+        // Errors are ignored since progress is purely informational
+        let mut stderr = io::stderr();
+        let _ = write!(stderr, "\r{}\x1b[K", line);
+        let _ = stderr.flush();
+    }
+}
+
+impl ProgressSink for StderrLine {
+    fn report(&mut self, report: &Report) {
+        self.draw(report);
+    }
+
+    fn finish(&mut self, report: &Report) {
+        self.draw(report);
+        eprintln!();
+    }
+}
+
+/// Progress tracker that long running loops report to.
+///
+/// Reports are throttled so calling `inc` once per iteration of a hot loop is
+/// cheap. A silent tracker never reports anything, which is what tests use.
+pub struct Progress {
+    sink: Option<Box<dyn ProgressSink>>,
+    done: u64,
+    total: Option<u64>,
+    start: Instant,
+    last_report: Instant,
+    calls_until_check: u32,
+}
+
+impl Progress {
+    /// How many updates to skip between checking the clock
+    const CHECK_STRIDE: u32 = 1 << 8;
+
+    /// Minimum time between two reports
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    pub fn new(sink: impl ProgressSink + 'static) -> Self {
+        let now = Instant::now();
+        Self {
+            sink: Some(Box::new(sink)),
+            done: 0,
+            total: None,
+            start: now,
+            last_report: now,
+            calls_until_check: 0,
+        }
+    }
+
+    pub fn silent() -> Self {
+        let now = Instant::now();
+        Self {
+            sink: None,
+            done: 0,
+            total: None,
+            start: now,
+            last_report: now,
+            calls_until_check: 0,
+        }
+    }
+
+    /// Progress line on stderr, or silent if stderr is not a terminal
+    pub fn stderr(label: &str) -> Self {
+        if io::stderr().is_terminal() {
+            Self::new(StderrLine::new(label))
+        } else {
+            Self::silent()
+        }
+    }
+
+    pub fn with_total(mut self, total: u64) -> Self {
+        self.set_total(Some(total));
+        self
+    }
+
+    pub fn set_total(&mut self, total: Option<u64>) {
+        self.total = total;
+    }
+
+    pub fn inc(&mut self, num_items: u64) {
+        self.set(self.done + num_items);
+    }
+
+    pub fn set(&mut self, done: u64) {
+        self.set_at(done, Instant::now)
+    }
+
+    /// Like `set`, with the clock given by the caller so that throttling can
+    /// be tested without sleeping
+    fn set_at(&mut self, done: u64, clock: impl FnOnce() -> Instant) {
+        self.done = done;
+        if self.sink.is_none() {
+            return;
+        } else if self.calls_until_check > 0 {
+            self.calls_until_check -= 1;
+            return;
+        }
+        self.calls_until_check = Self::CHECK_STRIDE;
+
+        let now = clock();
+        if now.duration_since(self.last_report) < Self::INTERVAL {
+            return;
+        }
+        self.last_report = now;
+
+        let report = self.report();
+        if let Some(sink) = self.sink.as_mut() {
+            sink.report(&report);
+        }
+    }
+
+    pub fn report(&self) -> Report {
+        Report {
+            done: self.done,
+            total: self.total,
+            elapsed: self.start.elapsed(),
+        }
+    }
+
+    /// Send a final report. This only has an effect the first time it's called
+    pub fn finish(&mut self) {
+        let report = self.report();
+        if let Some(mut sink) = self.sink.take() {
+            sink.finish(&report);
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[test]
+fn test_report() {
+    let report = Report {
+        done: 50,
+        total: Some(150),
+        elapsed: Duration::from_secs(2),
+    };
+    assert_eq!(report.rate(), 25.0);
+    assert_eq!(report.eta(), Some(Duration::from_secs(4)));
+
+    let report = Report {
+        total: None,
+        ..report
+    };
+    assert_eq!(report.eta(), None);
+}
+
+#[test]
+fn test_progress_throttle() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let reports = Rc::new(RefCell::new(Vec::new()));
+    let sink_reports = reports.clone();
+    let mut progress = Progress::new(move |r: &Report| sink_reports.borrow_mut().push(*r));
+    progress.set_total(Some(10));
+
+    // Nothing is reported until the interval has passed
+    let start = progress.start;
+    progress.set_at(5, || start);
+    assert!(reports.borrow().is_empty());
+
+    // The clock is only checked every so often
+    let later = start + Progress::INTERVAL;
+    for _ in 0..Progress::CHECK_STRIDE {
+        progress.set_at(5, || panic!("clock checked too early"));
+    }
+    assert!(reports.borrow().is_empty());
+    progress.set_at(10, || later);
+    assert_eq!(reports.borrow().len(), 1);
+    assert_eq!(reports.borrow()[0].done, 10);
+    assert_eq!(reports.borrow()[0].total, Some(10));
+}
@@ -0,0 +1,187 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{Add, Range, Sub};
+
+/// Integer type that ranges can be made of
+pub trait Bound: Copy + Default + Ord + Add<Output = Self> + Sub<Output = Self> {}
+
+impl<T: Copy + Default + Ord + Add<Output = T> + Sub<Output = T>> Bound for T {}
+
+/// Set of values stored as sorted half-open ranges. Overlapping and adjacent
+/// ranges are merged, so every range is separated from the next one by a gap
+#[derive(Clone, Default, Eq, Hash, PartialEq)]
+pub struct RangeSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: Bound> RangeSet<T> {
+    pub fn new() -> Self {
+        Self { ranges: Vec::new() }
+    }
+
+    /// Number of separate ranges
+    pub fn num_ranges(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Total length of all ranges
+    pub fn coverage(&self) -> T {
+        self.ranges
+            .iter()
+            .fold(T::default(), |sum, r| sum + (r.end - r.start))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Range<T>> + '_ {
+        self.ranges.iter().cloned()
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let i = self.ranges.partition_point(|r| r.start <= value);
+        i > 0 && value < self.ranges[i - 1].end
+    }
+
+    /// True if the whole range is in the set
+    pub fn contains_range(&self, range: Range<T>) -> bool {
+        if range.start >= range.end {
+            return true;
+        }
+        let i = self.ranges.partition_point(|r| r.start <= range.start);
+        i > 0 && range.end <= self.ranges[i - 1].end
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.start >= range.end {
+            return;
+        }
+
+        // Ranges that overlap or touch the new one are merged into it
+        let i = self.ranges.partition_point(|r| r.end < range.start);
+        let j = self.ranges.partition_point(|r| r.start <= range.end);
+        let mut merged = range;
+        if i < j {
+            merged.start = merged.start.min(self.ranges[i].start);
+            merged.end = merged.end.max(self.ranges[j - 1].end);
+        }
+        self.ranges.splice(i..j, std::iter::once(merged));
+    }
+
+    pub fn remove(&mut self, range: Range<T>) {
+        if range.start >= range.end {
+            return;
+        }
+
+        // Ranges that overlap the removed one are replaced by what is left of
+        // them on either side
+        let i = self.ranges.partition_point(|r| r.end <= range.start);
+        let j = self.ranges.partition_point(|r| r.start < range.end);
+        if i >= j {
+            return;
+        }
+
+        let mut rest = Vec::with_capacity(2);
+        if self.ranges[i].start < range.start {
+            rest.push(self.ranges[i].start..range.start);
+        }
+        if range.end < self.ranges[j - 1].end {
+            rest.push(range.end..self.ranges[j - 1].end);
+        }
+        self.ranges.splice(i..j, rest);
+    }
+
+    /// All values that are in either set
+    pub fn union(&self, other: &Self) -> Self {
+        let mut union = self.clone();
+        union.extend(other.iter());
+        union
+    }
+
+    /// All values that are in both sets
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.difference(&self.difference(other))
+    }
+
+    /// All values in this set that are not in the other
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut difference = self.clone();
+        for range in other.iter() {
+            difference.remove(range);
+        }
+        difference
+    }
+}
+
+impl<T: Bound> Extend<Range<T>> for RangeSet<T> {
+    fn extend<I: IntoIterator<Item = Range<T>>>(&mut self, iter: I) {
+        for range in iter {
+            self.insert(range);
+        }
+    }
+}
+
+impl<T: Bound> FromIterator<Range<T>> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RangeSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RangeSet")?;
+        f.debug_list().entries(self.ranges.iter()).finish()
+    }
+}
+
+#[test]
+fn test_insert_remove() {
+    let mut set: RangeSet<i32> = vec![5..8, 1..3, 3..4, 10..10].into_iter().collect();
+    assert_eq!(set.iter().collect::<Vec<_>>(), vec![1..4, 5..8]);
+    assert_eq!(set.coverage(), 6);
+
+    set.insert(4..5);
+    assert_eq!(set.iter().collect::<Vec<_>>(), vec![1..8]);
+    set.insert(-5..-3);
+    set.insert(20..30);
+    assert_eq!(set.num_ranges(), 3);
+
+    set.remove(2..4);
+    set.remove(25..40);
+    set.remove(-10..-4);
+    assert_eq!(format!("{:?}", set), "RangeSet[-4..-3, 1..2, 4..8, 20..25]");
+    set.remove(-100..100);
+    assert!(set.is_empty());
+}
+
+#[test]
+fn test_contains() {
+    let set: RangeSet<u32> = vec![2..4, 6..9].into_iter().collect();
+    assert!(!set.contains(1));
+    assert!(set.contains(2));
+    assert!(!set.contains(4));
+    assert!(set.contains(8));
+    assert!(!set.contains(9));
+    assert!(set.contains_range(6..9));
+    assert!(!set.contains_range(3..7));
+    assert!(set.contains_range(5..5));
+}
+
+#[test]
+fn test_set_operations() {
+    let a: RangeSet<i64> = vec![0..10, 20..30].into_iter().collect();
+    let b: RangeSet<i64> = std::iter::once(5..25).collect();
+    assert_eq!(a.union(&b).iter().collect::<Vec<_>>(), vec![0..30]);
+    assert_eq!(
+        a.difference(&b).iter().collect::<Vec<_>>(),
+        vec![0..5, 25..30]
+    );
+    assert_eq!(b.difference(&a).iter().collect::<Vec<_>>(), vec![10..20]);
+    assert_eq!(
+        a.intersection(&b).iter().collect::<Vec<_>>(),
+        vec![5..10, 20..25]
+    );
+}
@@ -0,0 +1,292 @@
+use crate::cli::Args;
+use crate::json::Value;
+use crate::parse::ParseError;
+#[cfg(test)]
+use crate::testing::SharedBuf;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+
+/// Records the state of a solver after every step as one JSON object per
+/// line (NDJSON), so that runs can be replayed or compared with `aoc replay`.
+///
+/// Every record starts with the current part, if one is set, and the step
+/// number within it, followed by the fields given by the solver. A disabled
+/// recorder ignores all steps, which is what solvers get unless `--record` is
+/// given.
+pub struct Recorder {
+    out: Option<Box<dyn Write>>,
+    part: Option<String>,
+    step: usize,
+}
+
+impl Recorder {
+    pub fn new(out: impl Write + 'static) -> Self {
+        Self {
+            out: Some(Box::new(out)),
+            part: None,
+            step: 0,
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self {
+            out: None,
+            part: None,
+            step: 0,
+        }
+    }
+
+    /// Recorder writing to the file given by `--record`, otherwise disabled
+    pub fn from_args(args: &Args) -> io::Result<Self> {
+        match args.record {
+            Some(ref path) => Ok(Self::new(BufWriter::new(File::create(path)?))),
+            None => Ok(Self::disabled()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.out.is_some()
+    }
+
+    /// Label the following records with the given part and count steps from
+    /// zero again
+    pub fn part(&mut self, name: &str) {
+        self.part = Some(name.to_owned());
+        self.step = 0;
+    }
+
+    /// Flush the recording and stop recording. Dropping the recorder flushes
+    /// it too, but any error is lost then
+    pub fn finish(&mut self) -> io::Result<()> {
+        match self.out.take() {
+            Some(mut out) => out.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Write a record for the next step. Prefer the `record!` macro which
+    /// skips building the fields when recording is disabled
+    pub fn write(&mut self, fields: Vec<(&str, Value)>) {
+        let out = match self.out.as_mut() {
+            Some(out) => out,
+            None => return,
+        };
+
+        let mut record = Vec::with_capacity(fields.len() + 2);
+        if let Some(part) = &self.part {
+            record.push(("part", Value::from(part.as_str())));
+        }
+        record.push(("step", Value::from(self.step)));
+        record.extend(fields);
+        self.step += 1;
+
+        // A recording with holes is useless for comparisons, so stop at the
+        // first error rather than skipping steps
+        if let Err(err) = writeln!(out, "{}", Value::object(record)) {
+            eprintln!("Recording stopped: {}", err);
+            self.out = None;
+        }
+    }
+}
+
+/// Write a record with named fields if recording is enabled:
+///
+/// ```
+/// # let mut recorder = aoc::record::Recorder::disabled();
+/// # let gen = 1;
+/// aoc::record!(recorder, gen = gen, pots = "#..#");
+/// ```
+#[macro_export]
+macro_rules! record {
+    ($recorder:expr $(, $key:ident = $value:expr)* $(,)?) => {
+        if $recorder.is_enabled() {
+            $recorder.write(vec![$((stringify!($key), $crate::json::Value::from($value))),*]);
+        }
+    };
+}
+
+/// Read records, one per non-empty line. Lines that aren't valid JSON give
+/// an `InvalidData` error with the line number
+pub fn read(reader: impl BufRead) -> impl Iterator<Item = io::Result<Value>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+        .map(|(i, line)| {
+            line?.parse().map_err(|error| {
+                let error = ParseError::AtLine {
+                    line: i + 1,
+                    error: Box::new(error),
+                };
+                io::Error::new(io::ErrorKind::InvalidData, error)
+            })
+        })
+}
+
+/// Where a record is in its recording, like `part A, step 12`
+pub fn location(record: &Value) -> String {
+    let step = match record.get("step") {
+        Some(step) => format!("step {}", step),
+        None => "step ?".to_owned(),
+    };
+    match record.get("part").and_then(Value::as_str) {
+        Some(part) => format!("part {}, {}", part, step),
+        None => step,
+    }
+}
+
+/// The first record where two recordings differ
+#[derive(Clone, Debug, PartialEq)]
+pub struct Difference {
+    /// Index of the record, counting from one
+    pub index: usize,
+    /// None if the recording ended before this record
+    pub left: Option<Value>,
+    pub right: Option<Value>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (left, right) = match (&self.left, &self.right) {
+            (Some(left), Some(right)) => (left, right),
+            (Some(record), None) | (None, Some(record)) => {
+                let ended = if self.left.is_none() { "Left" } else { "Right" };
+                return write!(
+                    f,
+                    "{} recording ends before record {} ({}): {}",
+                    ended,
+                    self.index,
+                    location(record),
+                    record
+                );
+            }
+            (None, None) => return write!(f, "Both recordings end at record {}", self.index),
+        };
+
+        write!(f, "Record {} ({}) differs:", self.index, location(left))?;
+        let fields = |record: &Value| match record {
+            Value::Object(fields) => fields.clone(),
+            other => vec![(String::new(), other.clone())],
+        };
+        let (left_fields, right_fields) = (fields(left), fields(right));
+
+        let mut keys: Vec<&String> = left_fields.iter().map(|(k, _)| k).collect();
+        for (key, _) in right_fields.iter() {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        for key in keys {
+            let find = |fields: &[(String, Value)]| {
+                fields
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .map_or("(missing)".to_owned(), |(_, v)| v.to_string())
+            };
+            let (l, r) = (find(&left_fields), find(&right_fields));
+            if l != r {
+                write!(f, "\n  {}: {} != {}", key, l, r)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compare two recordings record by record. Records are compared as JSON
+/// values, so formatting differences don't count
+pub fn first_difference(left: impl BufRead, right: impl BufRead) -> io::Result<Option<Difference>> {
+    let mut left = read(left);
+    let mut right = read(right);
+    for index in 1.. {
+        let (l, r) = match (left.next().transpose()?, right.next().transpose()?) {
+            (None, None) => return Ok(None),
+            pair => pair,
+        };
+        if l != r {
+            return Ok(Some(Difference {
+                index,
+                left: l,
+                right: r,
+            }));
+        }
+    }
+    unreachable!()
+}
+
+#[test]
+fn test_record() {
+    let buf = SharedBuf::new();
+    let mut recorder = Recorder::new(buf.clone());
+    crate::record!(recorder, gen = 0);
+    recorder.part("B");
+    crate::record!(recorder, gen = 5, pots = "#.#");
+    crate::record!(recorder, gen = 6, pots = "##");
+
+    let out = buf.text();
+    assert_eq!(
+        out,
+        concat!(
+            "{\"step\":0,\"gen\":0}\n",
+            "{\"part\":\"B\",\"step\":0,\"gen\":5,\"pots\":\"#.#\"}\n",
+            "{\"part\":\"B\",\"step\":1,\"gen\":6,\"pots\":\"##\"}\n",
+        )
+    );
+
+    let records: Vec<Value> = read(out.as_bytes()).collect::<io::Result<_>>().unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(location(&records[0]), "step 0");
+    assert_eq!(location(&records[2]), "part B, step 1");
+
+    let mut num_evaluated = 0;
+    let mut disabled = Recorder::disabled();
+    crate::record!(
+        disabled,
+        value = {
+            num_evaluated += 1;
+            num_evaluated
+        }
+    );
+    assert_eq!(num_evaluated, 0);
+}
+
+#[test]
+fn test_read_errors() {
+    let text = "{\"step\":0}\n\n{\"step\":1\n";
+    let records: Vec<_> = read(text.as_bytes()).collect();
+    assert!(records[0].is_ok());
+    let err = records[1].as_ref().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(err.to_string().starts_with("line 3"), "{}", err);
+}
+
+#[test]
+fn test_first_difference() {
+    let left = "{\"step\":0,\"score\":1}\n{\"step\":1,\"score\":5,\"marble\":2}\n";
+    let same = "{\"step\": 0, \"score\": 1}\n{\"step\": 1, \"score\": 5, \"marble\": 2}\n";
+    let right = "{\"step\":0,\"score\":1}\n{\"step\":1,\"score\":6,\"marble\":2}\n";
+    let short = "{\"step\":0,\"score\":1}\n";
+
+    assert_eq!(
+        first_difference(left.as_bytes(), same.as_bytes()).unwrap(),
+        None
+    );
+
+    let diff = first_difference(left.as_bytes(), right.as_bytes())
+        .unwrap()
+        .unwrap();
+    assert_eq!(diff.index, 2);
+    assert_eq!(
+        diff.to_string(),
+        "Record 2 (step 1) differs:\n  score: 5 != 6"
+    );
+
+    let diff = first_difference(short.as_bytes(), left.as_bytes())
+        .unwrap()
+        .unwrap();
+    assert_eq!(diff.left, None);
+    assert_eq!(
+        diff.to_string(),
+        "Left recording ends before record 2 (step 1): {\"step\":1,\"score\":5,\"marble\":2}"
+    );
+}
@@ -0,0 +1,199 @@
+use crate::collections::DisjointSet;
+use crate::geom::{Coord, Rect};
+use std::iter::FromIterator;
+
+/// Collection of possibly overlapping rectangles. Areas are computed using
+/// coordinate compression, so the cost depends on the number of rectangles
+/// rather than on how large they are
+#[derive(Clone, Debug, Default)]
+pub struct RectSet<T = i64> {
+    rects: Vec<Rect<T>>,
+}
+
+impl<T: Coord> RectSet<T> {
+    pub fn new() -> Self {
+        Self { rects: Vec::new() }
+    }
+
+    /// Add a rectangle and return its index
+    pub fn insert(&mut self, rect: Rect<T>) -> usize {
+        self.rects.push(rect);
+        self.rects.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.rects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    pub fn rects(&self) -> &[Rect<T>] {
+        &self.rects
+    }
+
+    /// Area covered by at least `n` rectangles
+    pub fn area_covered_by(&self, n: usize) -> T {
+        let rects: Vec<&Rect<T>> = self.rects.iter().filter(|r| !r.is_empty()).collect();
+        let ys = compress(rects.iter().flat_map(|r| [r.min.y, r.max.y]));
+
+        // Each rectangle enters the sweep at its left edge and leaves at its
+        // right edge. Leaving is sorted first, but it doesn't matter since no
+        // area is added between events at the same x
+        let mut events: Vec<(T, bool, usize)> = Vec::with_capacity(2 * rects.len());
+        for (i, r) in rects.iter().enumerate() {
+            events.push((r.min.x, true, i));
+            events.push((r.max.x, false, i));
+        }
+        events.sort();
+
+        // Number of rectangles covering each compressed row of the sweep line
+        let mut counts = vec![0usize; ys.len().saturating_sub(1)];
+        let mut area = T::default();
+        let mut prev_x = None;
+        for (x, enter, i) in events {
+            if let Some(prev_x) = prev_x {
+                area = area + (x - prev_x) * covered_length(&counts, &ys, n);
+            }
+            prev_x = Some(x);
+
+            let rows = index(&ys, rects[i].min.y)..index(&ys, rects[i].max.y);
+            for count in &mut counts[rows] {
+                if enter {
+                    *count += 1;
+                } else {
+                    *count -= 1;
+                }
+            }
+        }
+        area
+    }
+
+    /// Area covered by any rectangle
+    pub fn union_area(&self) -> T {
+        self.area_covered_by(1)
+    }
+
+    /// Area covered by two or more rectangles
+    pub fn overlap_area(&self) -> T {
+        self.area_covered_by(2)
+    }
+
+    /// Indices of all rectangles that don't overlap any other rectangle, in
+    /// insertion order
+    pub fn isolated(&self) -> Vec<usize> {
+        let mut overlapping = vec![false; self.rects.len()];
+        self.overlapping_pairs(|i, j| {
+            overlapping[i] = true;
+            overlapping[j] = true;
+        });
+        (0..self.rects.len()).filter(|&i| !overlapping[i]).collect()
+    }
+
+    /// Groups of rectangles that are connected through overlaps. Every
+    /// rectangle is part of exactly one cluster
+    pub fn clusters(&self) -> Vec<Vec<usize>> {
+        let mut clusters = DisjointSet::new(self.rects.len());
+        self.overlapping_pairs(|i, j| {
+            clusters.union(i, j);
+        });
+        clusters.components()
+    }
+
+    /// Call `f` for every pair of rectangles that overlap
+    fn overlapping_pairs(&self, mut f: impl FnMut(usize, usize)) {
+        let mut order: Vec<usize> = (0..self.rects.len()).collect();
+        order.sort_by_key(|&i| self.rects[i].min.x);
+
+        // Sweep from left to right while keeping the rectangles that the
+        // sweep line currently crosses
+        let mut active: Vec<usize> = Vec::new();
+        for i in order {
+            let rect = &self.rects[i];
+            active.retain(|&j| self.rects[j].max.x > rect.min.x);
+            for &j in &active {
+                if rect.intersects(&self.rects[j]) {
+                    f(i, j);
+                }
+            }
+            if !rect.is_empty() {
+                active.push(i);
+            }
+        }
+    }
+}
+
+impl<T: Coord> FromIterator<Rect<T>> for RectSet<T> {
+    fn from_iter<I: IntoIterator<Item = Rect<T>>>(iter: I) -> Self {
+        Self {
+            rects: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Sorted unique coordinates
+fn compress<T: Coord>(coords: impl Iterator<Item = T>) -> Vec<T> {
+    let mut coords: Vec<T> = coords.collect();
+    coords.sort();
+    coords.dedup();
+    coords
+}
+
+fn index<T: Coord>(coords: &[T], value: T) -> usize {
+    coords.binary_search(&value).unwrap()
+}
+
+/// Total length of the compressed rows that are covered at least `n` times
+fn covered_length<T: Coord>(counts: &[usize], ys: &[T], n: usize) -> T {
+    counts
+        .iter()
+        .enumerate()
+        .filter(|&(_, &count)| count >= n)
+        .fold(T::default(), |len, (i, _)| len + (ys[i + 1] - ys[i]))
+}
+
+#[test]
+fn test_areas() {
+    use crate::geom::Point2;
+
+    let set: RectSet = vec![
+        Rect::from_size(Point2::new(1, 3), 4, 4),
+        Rect::from_size(Point2::new(3, 1), 4, 4),
+        Rect::from_size(Point2::new(5, 5), 2, 2),
+        Rect::from_size(Point2::new(9, 9), 0, 5),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(set.union_area(), 32);
+    assert_eq!(set.overlap_area(), 4);
+    assert_eq!(set.area_covered_by(3), 0);
+    assert_eq!(set.isolated(), vec![2, 3]);
+    assert_eq!(set.clusters(), vec![vec![0, 1], vec![2], vec![3]]);
+
+    assert_eq!(RectSet::<i64>::new().union_area(), 0);
+    assert_eq!(RectSet::<i64>::new().isolated(), vec![]);
+}
+
+#[test]
+fn test_large_and_deep() {
+    use crate::geom::Point2;
+
+    // Many more overlaps than fit in a byte, far away from the origin
+    let mut set: RectSet = RectSet::new();
+    for i in 0..300 {
+        set.insert(Rect::from_size(
+            Point2::new(1_000_000_000_000 + i, -5_000_000_000),
+            1_000_000,
+            10_000_000_000,
+        ));
+    }
+    let lonely = set.insert(Rect::from_size(Point2::new(0, 0), 3, 3));
+    let touching = set.insert(Rect::from_size(Point2::new(3, 0), 3, 3));
+
+    assert_eq!(set.area_covered_by(300), (1_000_000 - 299) * 10_000_000_000);
+    assert_eq!(set.area_covered_by(301), 0);
+    assert_eq!(set.union_area(), (1_000_000 + 299) * 10_000_000_000 + 9 + 9);
+    assert_eq!(set.isolated(), vec![lonely, touching]);
+    assert_eq!(set.clusters().len(), 3);
+}
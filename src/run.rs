@@ -0,0 +1,71 @@
+use crate::animate::Player;
+use crate::cli::Args;
+use crate::overflow::Overflow;
+use crate::progress::Progress;
+use crate::record::Recorder;
+use std::io;
+
+/// Everything a solver needs besides its input: how to handle overflow and
+/// where to report progress, animation frames and recorded steps.
+///
+/// A silent context checks for overflow and reports nothing, which is what
+/// tests use.
+pub struct RunContext {
+    pub overflow: Overflow,
+    pub progress: Progress,
+    pub animation: Player,
+    pub recorder: Recorder,
+    show_progress: bool,
+}
+
+impl RunContext {
+    pub fn silent() -> Self {
+        Self {
+            overflow: Overflow::Checked,
+            progress: Progress::silent(),
+            animation: Player::disabled(),
+            recorder: Recorder::disabled(),
+            show_progress: false,
+        }
+    }
+
+    /// Context as requested on the command line. Progress is shown on stderr
+    /// if it's a terminal
+    pub fn from_args(args: &Args) -> io::Result<Self> {
+        Ok(Self {
+            overflow: args.overflow,
+            progress: Progress::silent(),
+            animation: Player::from_args(args),
+            recorder: Recorder::from_args(args)?,
+            show_progress: true,
+        })
+    }
+
+    /// Start a part of the puzzle. Progress starts over and records are
+    /// labeled with the part
+    pub fn part(&mut self, name: &str) {
+        self.progress = if self.show_progress {
+            Progress::stderr(&format!("Part {}", name))
+        } else {
+            Progress::silent()
+        };
+        self.recorder.part(name);
+    }
+
+    /// Finish reporting. Unlike dropping the context, this returns the error
+    /// if the recording can't be written
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.progress.finish();
+        self.animation.finish();
+        self.recorder.finish()
+    }
+}
+
+#[test]
+fn test_silent() {
+    let mut context = RunContext::silent();
+    context.part("A");
+    assert!(!context.animation.is_enabled());
+    assert!(!context.recorder.is_enabled());
+    assert!(context.finish().is_ok());
+}
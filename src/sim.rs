@@ -0,0 +1,177 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Event waiting in the queue. Ordered so that the heap pops the earliest
+/// event first, and events at the same time in the order they were scheduled
+struct Scheduled<E> {
+    time: u64,
+    seq: u64,
+    event: E,
+}
+
+impl<E> PartialEq for Scheduled<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<E> Eq for Scheduled<E> {}
+
+impl<E> PartialOrd for Scheduled<E> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<E> Ord for Scheduled<E> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.time, other.seq).cmp(&(self.time, self.seq))
+    }
+}
+
+/// Discrete-event simulation of a world with state `S` that changes through
+/// events of type `E`. Events are processed in time order, and events that
+/// happen at the same time are processed in the order they were scheduled.
+/// This makes every run deterministic, and means that an event scheduled with
+/// no delay runs after everything else that is already due at that time
+pub struct Simulation<S, E> {
+    pub state: S,
+    now: u64,
+    next_seq: u64,
+    queue: BinaryHeap<Scheduled<E>>,
+}
+
+impl<S, E> Simulation<S, E> {
+    pub fn new(state: S) -> Self {
+        Self {
+            state,
+            now: 0,
+            next_seq: 0,
+            queue: BinaryHeap::new(),
+        }
+    }
+
+    /// Time of the event that is being or was last processed
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Number of events waiting to be processed
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Time of the next event
+    pub fn next_time(&self) -> Option<u64> {
+        self.queue.peek().map(|s| s.time)
+    }
+
+    /// Schedule an event `delay` time units from now
+    pub fn schedule(&mut self, delay: u64, event: E) {
+        self.schedule_at(self.now + delay, event);
+    }
+
+    /// Schedule an event at an absolute time. Panics if the time has passed
+    pub fn schedule_at(&mut self, time: u64, event: E) {
+        assert!(
+            time >= self.now,
+            "Can't schedule event at {} since it's already {}",
+            time,
+            self.now
+        );
+        self.queue.push(Scheduled {
+            time,
+            seq: self.next_seq,
+            event,
+        });
+        self.next_seq += 1;
+    }
+
+    /// Advance the clock to the next event and process it. Returns false if
+    /// there were no events left
+    pub fn step(&mut self, handle: impl FnOnce(&mut Self, E)) -> bool {
+        match self.queue.pop() {
+            Some(scheduled) => {
+                self.now = scheduled.time;
+                handle(self, scheduled.event);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Process events until there are none left. Returns the number of
+    /// processed events
+    pub fn run(&mut self, handle: impl FnMut(&mut Self, E)) -> usize {
+        self.run_until(|_| false, handle)
+    }
+
+    /// Process events until there are none left or `stop` returns true. The
+    /// condition is checked before every event. Returns the number of
+    /// processed events
+    pub fn run_until(
+        &mut self,
+        mut stop: impl FnMut(&Self) -> bool,
+        mut handle: impl FnMut(&mut Self, E),
+    ) -> usize {
+        let mut num_events = 0;
+        while !stop(self) && self.step(&mut handle) {
+            num_events += 1;
+        }
+        num_events
+    }
+}
+
+#[test]
+fn test_ordering() {
+    let mut sim = Simulation::new(Vec::new());
+    sim.schedule(5, 'c');
+    sim.schedule(1, 'a');
+    sim.schedule(5, 'd');
+    sim.schedule_at(1, 'b');
+    assert_eq!(sim.next_time(), Some(1));
+
+    let num_events = sim.run(|sim, event| {
+        sim.state.push((sim.now(), event));
+        if event == 'a' {
+            // Runs after b, which was scheduled for the same time earlier
+            sim.schedule(0, 'x');
+        }
+    });
+    assert_eq!(num_events, 5);
+    assert!(sim.is_idle());
+    assert_eq!(
+        sim.state,
+        vec![(1, 'a'), (1, 'b'), (1, 'x'), (5, 'c'), (5, 'd')]
+    );
+}
+
+#[test]
+fn test_run_until() {
+    // Counter that reschedules itself forever
+    let mut sim = Simulation::new(0);
+    sim.schedule(0, ());
+    let num_events = sim.run_until(
+        |sim| sim.next_time() > Some(30),
+        |sim, ()| {
+            sim.state += 1;
+            sim.schedule(7, ());
+        },
+    );
+    assert_eq!(num_events, 5);
+    assert_eq!(sim.state, 5);
+    assert_eq!(sim.now(), 28);
+    assert_eq!(sim.pending(), 1);
+}
+
+#[test]
+#[should_panic]
+fn test_schedule_in_past() {
+    let mut sim = Simulation::new(());
+    sim.schedule(3, ());
+    sim.step(|sim, ()| sim.schedule_at(2, ()));
+}
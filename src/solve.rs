@@ -0,0 +1,330 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+
+/// Outcome of assigning every key a distinct value from its candidates
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Matching<K, V> {
+    /// Exactly one assignment is possible
+    Unique(BTreeMap<K, V>),
+    /// More than one assignment is possible. Holds two of them
+    Ambiguous(Vec<BTreeMap<K, V>>),
+    Unsatisfiable,
+}
+
+impl<K, V> Matching<K, V> {
+    /// The assignment, or an error if there isn't exactly one
+    pub fn unique(self) -> Result<BTreeMap<K, V>, MatchingError> {
+        match self {
+            Matching::Unique(assignment) => Ok(assignment),
+            Matching::Ambiguous(_) => Err(MatchingError::Ambiguous),
+            Matching::Unsatisfiable => Err(MatchingError::Unsatisfiable),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MatchingError {
+    Ambiguous,
+    Unsatisfiable,
+}
+
+impl fmt::Display for MatchingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatchingError::Ambiguous => write!(f, "more than one assignment is possible"),
+            MatchingError::Unsatisfiable => write!(f, "no assignment is possible"),
+        }
+    }
+}
+
+impl Error for MatchingError {}
+
+/// Candidate sets converted to indices. Values are sorted so that the search
+/// order doesn't depend on the order of the input sets
+struct Problem<K, V> {
+    keys: Vec<K>,
+    values: Vec<V>,
+    options: Vec<Vec<usize>>,
+}
+
+impl<K: Clone + Ord, V: Clone + Ord> Problem<K, V> {
+    fn new<I>(candidates: impl IntoIterator<Item = (K, I)>) -> Self
+    where
+        I: IntoIterator<Item = V>,
+    {
+        let candidates: BTreeMap<K, BTreeSet<V>> = candidates
+            .into_iter()
+            .map(|(key, values)| (key, values.into_iter().collect()))
+            .collect();
+        let values: Vec<V> = candidates
+            .values()
+            .flatten()
+            .cloned()
+            .collect::<BTreeSet<V>>()
+            .into_iter()
+            .collect();
+        let options = candidates
+            .values()
+            .map(|set| {
+                set.iter()
+                    .map(|v| values.binary_search(v).unwrap())
+                    .collect()
+            })
+            .collect();
+        Self {
+            keys: candidates.into_keys().collect(),
+            values,
+            options,
+        }
+    }
+
+    fn to_map(&self, assignment: &[Option<usize>]) -> BTreeMap<K, V> {
+        self.keys
+            .iter()
+            .zip(assignment)
+            .filter_map(|(k, v)| Some((k.clone(), self.values[(*v)?].clone())))
+            .collect()
+    }
+
+    /// Remove the value of every key that has a single option left from the
+    /// options of all other keys, until no more keys are resolved. Every
+    /// removal is reported with the key, the value and the number of options
+    /// the key has left
+    fn propagate(&mut self, mut eliminate: impl FnMut(&K, &V, usize)) {
+        let mut resolved = vec![false; self.keys.len()];
+        while let Some(key) =
+            (0..self.keys.len()).find(|&k| !resolved[k] && self.options[k].len() == 1)
+        {
+            resolved[key] = true;
+            let value = self.options[key][0];
+            for other in (0..self.keys.len()).filter(|&k| k != key) {
+                let options = &mut self.options[other];
+                if let Some(i) = options.iter().position(|&v| v == value) {
+                    options.remove(i);
+                    eliminate(&self.keys[other], &self.values[value], options.len());
+                }
+            }
+        }
+    }
+
+    /// Largest possible assignment, found using augmenting paths
+    fn maximum(&self) -> Vec<Option<usize>> {
+        let mut owner: Vec<Option<usize>> = vec![None; self.values.len()];
+        for key in 0..self.keys.len() {
+            let mut visited = vec![false; self.values.len()];
+            self.augment(key, &mut owner, &mut visited);
+        }
+
+        let mut assignment = vec![None; self.keys.len()];
+        for (value, key) in owner.into_iter().enumerate() {
+            if let Some(key) = key {
+                assignment[key] = Some(value);
+            }
+        }
+        assignment
+    }
+
+    /// Try to give the key a value, possibly by moving the current owner of
+    /// a value to another one
+    fn augment(&self, key: usize, owner: &mut [Option<usize>], visited: &mut [bool]) -> bool {
+        for &value in &self.options[key] {
+            if visited[value] {
+                continue;
+            }
+            visited[value] = true;
+
+            let free = match owner[value] {
+                None => true,
+                Some(other) => self.augment(other, owner, visited),
+            };
+            if free {
+                owner[value] = Some(key);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Find up to `limit` complete assignments. The key with the fewest
+    /// remaining options is always assigned next, which means that keys with
+    /// only one option left are resolved before any guessing happens
+    fn search(
+        &self,
+        assignment: &mut Vec<Option<usize>>,
+        used: &mut Vec<bool>,
+        limit: usize,
+        solutions: &mut Vec<Vec<Option<usize>>>,
+    ) {
+        if solutions.len() >= limit {
+            return;
+        }
+
+        let next = (0..self.keys.len())
+            .filter(|&k| assignment[k].is_none())
+            .min_by_key(|&k| self.options[k].iter().filter(|&&v| !used[v]).count());
+        let key = match next {
+            Some(key) => key,
+            None => {
+                solutions.push(assignment.clone());
+                return;
+            }
+        };
+
+        for &value in &self.options[key] {
+            if used[value] {
+                continue;
+            }
+            assignment[key] = Some(value);
+            used[value] = true;
+            self.search(assignment, used, limit, solutions);
+            used[value] = false;
+            assignment[key] = None;
+        }
+    }
+
+    fn solutions(&self, limit: usize) -> Vec<BTreeMap<K, V>> {
+        let mut solutions = Vec::new();
+        self.search(
+            &mut vec![None; self.keys.len()],
+            &mut vec![false; self.values.len()],
+            limit,
+            &mut solutions,
+        );
+        solutions.iter().map(|s| self.to_map(s)).collect()
+    }
+}
+
+/// Assign every key a distinct value from its set of candidates, and report
+/// whether that can be done in exactly one way
+pub fn solve_matching<K, V, I>(candidates: impl IntoIterator<Item = (K, I)>) -> Matching<K, V>
+where
+    K: Clone + Ord,
+    V: Clone + Ord,
+    I: IntoIterator<Item = V>,
+{
+    solve_matching_with(candidates, |_, _, _| {})
+}
+
+/// Like `solve_matching`, but calls `eliminate` with the key, the value and
+/// the number of remaining candidates whenever propagation removes a value
+/// from the candidates of a key
+pub fn solve_matching_with<K, V, I>(
+    candidates: impl IntoIterator<Item = (K, I)>,
+    eliminate: impl FnMut(&K, &V, usize),
+) -> Matching<K, V>
+where
+    K: Clone + Ord,
+    V: Clone + Ord,
+    I: IntoIterator<Item = V>,
+{
+    let mut problem = Problem::new(candidates);
+    problem.propagate(eliminate);
+
+    // Finding a maximum matching is cheap, and rules out the cases where the
+    // search would have to exhaust every combination to find nothing
+    if problem.maximum().iter().any(Option::is_none) {
+        return Matching::Unsatisfiable;
+    }
+
+    let mut solutions = problem.solutions(2);
+    if solutions.len() == 1 {
+        Matching::Unique(solutions.pop().unwrap())
+    } else {
+        Matching::Ambiguous(solutions)
+    }
+}
+
+/// All ways to assign every key a distinct value from its candidates, in
+/// lexicographic order of the sorted values
+pub fn all_matchings<K, V, I>(candidates: impl IntoIterator<Item = (K, I)>) -> Vec<BTreeMap<K, V>>
+where
+    K: Clone + Ord,
+    V: Clone + Ord,
+    I: IntoIterator<Item = V>,
+{
+    Problem::new(candidates).solutions(usize::MAX)
+}
+
+/// Assign distinct values to as many keys as possible. Keys that can't be
+/// given a value are left out
+pub fn maximum_matching<K, V, I>(candidates: impl IntoIterator<Item = (K, I)>) -> BTreeMap<K, V>
+where
+    K: Clone + Ord,
+    V: Clone + Ord,
+    I: IntoIterator<Item = V>,
+{
+    let problem = Problem::new(candidates);
+    problem.to_map(&problem.maximum())
+}
+
+#[cfg(test)]
+fn candidates(spec: &[(u8, &str)]) -> Vec<(u8, Vec<char>)> {
+    spec.iter()
+        .map(|&(k, v)| (k, v.chars().collect()))
+        .collect()
+}
+
+#[test]
+fn test_unique() {
+    // Only one key is fixed to begin with, the rest follow from it
+    let input = candidates(&[(1, "ab"), (2, "bc"), (3, "c"), (4, "ade")]);
+    let expected = vec![(1, 'a'), (2, 'b'), (3, 'c'), (4, 'd')];
+    assert_eq!(
+        solve_matching(input.clone()),
+        Matching::Ambiguous(vec![
+            expected.iter().cloned().collect(),
+            vec![(1, 'a'), (2, 'b'), (3, 'c'), (4, 'e')]
+                .into_iter()
+                .collect()
+        ])
+    );
+
+    let input = candidates(&[(1, "ab"), (2, "bc"), (3, "c"), (4, "ad")]);
+    assert_eq!(
+        solve_matching(input.clone()),
+        Matching::Unique(expected.iter().cloned().collect())
+    );
+    assert_eq!(maximum_matching(input), expected.into_iter().collect());
+}
+
+#[test]
+fn test_eliminate() {
+    let input = candidates(&[(1, "ab"), (2, "bc"), (3, "c"), (4, "ad")]);
+    let mut eliminated = Vec::new();
+    let matching = solve_matching_with(input, |&k, &v, remaining| {
+        eliminated.push((k, v, remaining))
+    });
+    assert_eq!(eliminated, vec![(2, 'c', 1), (1, 'b', 1), (4, 'a', 1)]);
+    assert_eq!(matching.unique().unwrap().len(), 4);
+    assert_eq!(
+        solve_matching(candidates(&[(1, "a"), (2, "a")])).unique(),
+        Err(MatchingError::Unsatisfiable)
+    );
+}
+
+#[test]
+fn test_ambiguous() {
+    let input = candidates(&[(1, "ab"), (2, "ab"), (3, "c")]);
+    let expected: Vec<BTreeMap<u8, char>> = vec![
+        vec![(1, 'a'), (2, 'b'), (3, 'c')].into_iter().collect(),
+        vec![(1, 'b'), (2, 'a'), (3, 'c')].into_iter().collect(),
+    ];
+    assert_eq!(
+        solve_matching(input.clone()),
+        Matching::Ambiguous(expected.clone())
+    );
+    assert_eq!(all_matchings(input), expected);
+}
+
+#[test]
+fn test_unsatisfiable() {
+    let input = candidates(&[(1, "ab"), (2, "ab"), (3, "ab"), (4, "cd")]);
+    assert_eq!(solve_matching(input.clone()), Matching::Unsatisfiable);
+    assert_eq!(all_matchings(input.clone()), vec![]);
+    assert_eq!(maximum_matching(input).len(), 3);
+    assert_eq!(
+        solve_matching(candidates(&[(1, "")])),
+        Matching::<u8, char>::Unsatisfiable
+    );
+}
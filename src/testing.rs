@@ -0,0 +1,39 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// Writer that can be inspected after it has been given away. Players and
+/// recorders take ownership of their output, so tests give them a clone of
+/// this and look at what was written through the original
+#[derive(Clone, Default)]
+pub struct SharedBuf(Rc<RefCell<Vec<u8>>>);
+
+impl SharedBuf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything written so far. Panics if it isn't valid UTF-8
+    pub fn text(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+}
+
+impl Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_shared_buf() {
+    let buf = SharedBuf::new();
+    let mut out = buf.clone();
+    write!(out, "{} ", 1).unwrap();
+    write!(out, "{}", 2).unwrap();
+    assert_eq!(buf.text(), "1 2");
+}
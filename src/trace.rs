@@ -0,0 +1,124 @@
+use crate::cli::Args;
+use crate::json::Value;
+use lazy_static::lazy_static;
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Level {
+    Info = 1,
+    Debug = 2,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Debug => "debug",
+        }
+    }
+}
+
+static VERBOSITY: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    static ref SINK: Mutex<Option<Box<dyn Write + Send>>> = Mutex::new(None);
+}
+
+/// Start tracing events up to the given verbosity to the given sink
+pub fn init(verbosity: usize, sink: Box<dyn Write + Send>) {
+    *SINK.lock().unwrap() = Some(sink);
+    VERBOSITY.store(verbosity, Ordering::Relaxed);
+}
+
+/// Tracing is off unless enabled using `-v` (info) or `-vv` (debug). Events
+/// go to stderr unless `--trace-file` is given
+pub fn init_from_args(args: &Args) -> io::Result<()> {
+    if args.verbosity == 0 {
+        return Ok(());
+    }
+    let sink: Box<dyn Write + Send> = match args.trace_file {
+        Some(ref path) => Box::new(LineWriter::new(File::create(path)?)),
+        None => Box::new(io::stderr()),
+    };
+    init(args.verbosity, sink);
+    Ok(())
+}
+
+pub fn enabled(level: Level) -> bool {
+    VERBOSITY.load(Ordering::Relaxed) >= level as usize
+}
+
+/// Write an event. Prefer the `trace!` macro which skips building the fields
+/// when the level is disabled
+pub fn emit(level: Level, target: &str, event: &str, fields: Vec<(&str, Value)>) {
+    if let Some(sink) = SINK.lock().unwrap().as_mut() {
+        // Tracing must never take down a solver, so write errors are ignored
+        let _ = write_event(sink, level, target, event, fields);
+    }
+}
+
+/// Write an event as a single line of JSON
+fn write_event(
+    sink: &mut impl Write,
+    level: Level,
+    target: &str,
+    event: &str,
+    fields: Vec<(&str, Value)>,
+) -> io::Result<()> {
+    let mut record = vec![
+        ("level", Value::from(level.name())),
+        ("target", Value::from(target)),
+        ("event", Value::from(event)),
+    ];
+    record.extend(fields);
+    writeln!(sink, "{}", Value::object(record))
+}
+
+/// Emit a trace event with named fields if the level is enabled:
+///
+/// ```
+/// # let gen = 1;
+/// aoc::trace!(Info, "generation", gen = gen, pots = "#..#");
+/// ```
+#[macro_export]
+macro_rules! trace {
+    ($level:ident, $event:expr $(, $key:ident = $value:expr)* $(,)?) => {
+        if $crate::trace::enabled($crate::trace::Level::$level) {
+            $crate::trace::emit(
+                $crate::trace::Level::$level,
+                module_path!(),
+                $event,
+                vec![$((stringify!($key), $crate::json::Value::from($value))),*],
+            );
+        }
+    };
+}
+
+#[test]
+fn test_write_event() {
+    let mut sink = Vec::new();
+    let fields = vec![("player", Value::from(3)), ("score", Value::from(32usize))];
+    write_event(&mut sink, Level::Info, "aoc::trace", "marble", fields).unwrap();
+    assert_eq!(
+        String::from_utf8(sink).unwrap(),
+        "{\"level\":\"info\",\"target\":\"aoc::trace\",\"event\":\"marble\",\"player\":3,\"score\":32}\n"
+    );
+}
+
+#[test]
+fn test_disabled() {
+    // Tests never initialize tracing, so fields must not even be evaluated
+    let mut num_evaluated = 0;
+    crate::trace!(
+        Info,
+        "skipped",
+        value = {
+            num_evaluated += 1;
+            num_evaluated
+        }
+    );
+    assert_eq!(num_evaluated, 0);
+}
@@ -8,7 +8,7 @@ where
     S: AsRef<OsStr>,
 {
     let output = Command::new("cargo")
-        .args(&["run", "-q", "--bin", &format!("day{}", day)])
+        .args(["run", "-q", "--bin", &format!("day{}", day)])
         .args(args)
         .output()
         .or(Err(()))?;
@@ -20,7 +20,7 @@ where
         match line.chars().take(8).last().unwrap() {
             'A' => a = Some(line.chars().skip(10).collect()),
             'B' => b = Some(line.chars().skip(10).collect()),
-            c @ _ => panic!("Unknown answer letter: {:?}", c),
+            c => panic!("Unknown answer letter: {:?}", c),
         }
     }
 
@@ -37,68 +37,75 @@ fn ab(a: &str, b: &str) -> (String, Option<String>) {
 
 #[test]
 fn test_day1() {
-    assert_eq!(run_day(1, &["data/day1.txt"]).unwrap(), ab("585", "83173"));
+    assert_eq!(run_day(1, ["data/day1.txt"]).unwrap(), ab("585", "83173"));
 }
 
 #[test]
 fn test_day2() {
     assert_eq!(
-        run_day(2, &["data/day2.txt"]).unwrap(),
+        run_day(2, ["data/day2.txt"]).unwrap(),
         ab("4712", "lufjygedpvfbhftxiwnaorzmq")
     );
 }
 
 #[test]
 fn test_day3() {
-    assert_eq!(run_day(3, &["data/day3.txt"]).unwrap(), ab("105231", "164"));
+    assert_eq!(run_day(3, ["data/day3.txt"]).unwrap(), ab("105231", "164"));
 }
 
 #[test]
 fn test_day4() {
-    assert_eq!(
-        run_day(4, &["data/day4.txt"]).unwrap(),
-        ab("19025", "23776")
-    );
+    assert_eq!(run_day(4, ["data/day4.txt"]).unwrap(), ab("19025", "23776"));
 }
 
 #[test]
 fn test_day5() {
-    assert_eq!(run_day(5, &["data/day5.txt"]).unwrap(), ab("10888", "6952"));
+    assert_eq!(run_day(5, ["data/day5.txt"]).unwrap(), ab("10888", "6952"));
 }
 
 #[test]
 fn test_day7() {
     assert_eq!(
-        run_day(7, &["data/day7.txt"]).unwrap(),
-        a("JKNSTHCBGRVDXWAYFOQLMPZIUE")
+        run_day(7, ["data/day7.txt"]).unwrap(),
+        ab("JKNSTHCBGRVDXWAYFOQLMPZIUE", "755")
     );
 }
 
 #[test]
 fn test_day8() {
+    assert_eq!(run_day(8, ["data/day8.txt"]).unwrap(), ab("37905", "33891"));
+}
+
+#[test]
+fn test_day9() {
     assert_eq!(
-        run_day(8, &["data/day8.txt"]).unwrap(),
-        ab("37905", "33891")
+        run_day(9, ["data/day9.txt"]).unwrap(),
+        ab("425688", "3526561003")
     );
 }
 
 #[test]
-fn test_day9() {
+fn test_day9_args() {
     assert_eq!(
-        run_day(9, &["411", "71170"]).unwrap(),
+        run_day(9, ["411", "71170"]).unwrap(),
         ab("425688", "3526561003")
     );
 }
 
 #[test]
 fn test_day11() {
-    assert_eq!(run_day(11, &["5535"]).unwrap(), a("19,41"));
+    assert_eq!(run_day(11, ["data/day11.txt"]).unwrap(), a("19,41"));
+}
+
+#[test]
+fn test_day11_args() {
+    assert_eq!(run_day(11, ["5535"]).unwrap(), a("19,41"));
 }
 
 #[test]
 fn test_day12() {
     assert_eq!(
-        run_day(12, &["data/day12.txt"]).unwrap(),
+        run_day(12, ["data/day12.txt"]).unwrap(),
         ab("3276", "3750000001113")
     );
 }
@@ -106,12 +113,20 @@ fn test_day12() {
 #[test]
 fn test_day14() {
     assert_eq!(
-        run_day(14, &["864801"]).unwrap(),
+        run_day(14, ["data/day14.txt"]).unwrap(),
+        ab("1611732174", "20279772")
+    );
+}
+
+#[test]
+fn test_day14_args() {
+    assert_eq!(
+        run_day(14, ["864801"]).unwrap(),
         ab("1611732174", "20279772")
     );
 }
 
 #[test]
 fn test_day16() {
-    assert_eq!(run_day(16, &["data/day16.txt"]).unwrap(), ab("607", "577"));
+    assert_eq!(run_day(16, ["data/day16.txt"]).unwrap(), ab("607", "577"));
 }